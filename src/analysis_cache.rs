@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use crate::ugi_engine::SearchInfo;
+use crate::Move;
+
+// Constants
+pub const CACHE_PATH: &str = "./analysis_cache.txt";
+
+// Caches the deepest search seen for every analysed position
+// Positions are keyed by their boardstate string and the side that was searched
+pub struct AnalysisCache {
+    entries: HashMap<String, SearchInfo>,
+
+}
+
+impl AnalysisCache {
+    pub fn new() -> AnalysisCache {
+        return AnalysisCache {
+            entries: HashMap::new(),
+
+        };
+
+    }
+
+    pub fn key(boardstate_str: &str, side: f64) -> String {
+        return format!("{} {}", boardstate_str, side);
+
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SearchInfo> {
+        return self.entries.get(key);
+
+    }
+
+    // The deepest cached score of a position from P1's perspective, searched for either side
+    pub fn p1_score(&self, boardstate_str: &str) -> Option<f64> {
+        let mut best: Option<(f64, f64)> = None; // (ply, p1 score)
+        for side in [1.0, -1.0] {
+            if let Some(search_info) = self.get(&AnalysisCache::key(boardstate_str, side)) {
+                if let (Some(ply), Some(score)) = (search_info.ply, search_info.score) {
+                    if best.is_none_or(|(best_ply, _)| ply > best_ply) {
+                        best = Some((ply, score * side));
+
+                    }
+
+                }
+
+            }
+
+        }
+
+        return best.map(|(_, score)| score);
+
+    }
+
+    // Stores the search if it is deeper than the cached one, so a finished search isn't replaced by a restart at the same depth
+    // Returns true if the cache was updated
+    pub fn insert(&mut self, key: &str, search_info: &SearchInfo) -> bool {
+        if search_info.ply.is_none() {
+            return false;
+
+        }
+
+        if let Some(cached) = self.entries.get(key) {
+            if cached.ply >= search_info.ply {
+                return false;
+
+            }
+
+        }
+
+        self.entries.insert(key.to_string(), search_info.clone());
+
+        return true;
+
+    }
+
+    // ===== Persistence =====
+
+    // Loads a cache saved with `save`, skipping any malformed lines
+    pub fn load(path: &str) -> AnalysisCache {
+        let mut cache = AnalysisCache::new();
+
+        if let Ok(data) = fs::read_to_string(path) {
+            for line in data.lines() {
+                let parts: Vec<&str> = line.splitn(3, ' ').collect();
+                if parts.len() != 3 {
+                    continue;
+
+                }
+
+                let key = format!("{} {}", parts[0], parts[1]);
+                if let Some(search_info) = parse_cache_info(parts[2]) {
+                    cache.entries.insert(key, search_info);
+
+                }
+
+            }
+
+        }
+
+        return cache;
+
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut data = String::new();
+        for (key, search_info) in self.entries.iter() {
+            data.push_str(&format!("{} {}\n", key, cache_info_str(search_info)));
+
+        }
+
+        return fs::write(path, data);
+
+    }
+
+}
+
+// Writes a `SearchInfo` in the same key/value layout as an engine info line
+fn cache_info_str(search_info: &SearchInfo) -> String {
+    let mut parts = vec![];
+
+    if let Some(ply) = search_info.ply {
+        parts.push(format!("ply {}", ply));
+
+    }
+    if let Some(best_move) = &search_info.best_move {
+        let mv_str = best_move.iter().map(|i| i.to_string()).collect::<Vec<String>>().join("|");
+        parts.push(format!("bestmove {}", mv_str));
+
+    }
+    if let Some(score) = search_info.score {
+        parts.push(format!("score {}", score));
+
+    }
+    if let Some(nodes) = search_info.nodes {
+        parts.push(format!("nodes {}", nodes));
+
+    }
+    if let Some(nps) = search_info.nps {
+        parts.push(format!("nps {}", nps));
+
+    }
+    if let Some(abf) = search_info.abf {
+        parts.push(format!("abf {}", abf));
+
+    }
+    if let Some(beta_cuts) = search_info.beta_cuts {
+        parts.push(format!("beta_cuts {}", beta_cuts));
+
+    }
+    if let Some(time) = search_info.time {
+        parts.push(format!("time {}", time));
+
+    }
+
+    return parts.join(" ");
+
+}
+
+// Moves are stored already in board orientation, so they are never flipped here
+fn parse_cache_info(info_str: &str) -> Option<SearchInfo> {
+    let mut search_info = SearchInfo::new();
+
+    let raw_cmds: Vec<&str> = info_str.split_whitespace().collect();
+    for group in raw_cmds.chunks(2) {
+        if group.len() != 2 {
+            return None;
+
+        }
+
+        match group[0] {
+            "bestmove" => {
+                let mv: Result<Move, _> = group[1].split("|").map(|x| x.parse::<usize>()).collect();
+                search_info.best_move = Some(mv.ok()?);
+
+            },
+            key => {
+                let value = group[1].parse::<f64>().ok()?;
+                match key {
+                    "ply" => { search_info.ply = Some(value) },
+                    "score" => { search_info.score = Some(value) },
+                    "nodes" => { search_info.nodes = Some(value) },
+                    "nps" => { search_info.nps = Some(value) },
+                    "abf" => { search_info.abf = Some(value) },
+                    "beta_cuts" => { search_info.beta_cuts = Some(value) },
+                    "time" => { search_info.time = Some(value) },
+                    _ => {}
+
+                }
+
+            }
+
+        }
+
+    }
+
+    // Entries without a depth can't be compared to new searches
+    search_info.ply?;
+
+    return Some(search_info);
+
+}
@@ -0,0 +1,67 @@
+// Constants
+pub const ANIM_SPEEDS: [f32; 4] = [0.0, 300.0, 600.0, 1200.0]; // Off, Slow, Normal, Fast (board pixels per second)
+pub const FAST_SIM_TIME: f32 = 1.0; // Auto play with less time per move than this is not animated
+
+// Moves one piece along a path of board positions
+#[derive(Debug, Clone)]
+pub struct PieceAnimation {
+    pub id: usize,
+    path: Vec<(f32, f32)>,
+    segment: usize,
+    traveled: f32,
+
+}
+
+impl PieceAnimation {
+    pub fn new(id: usize, path: Vec<(f32, f32)>) -> PieceAnimation {
+        return PieceAnimation {
+            id,
+            path,
+            segment: 0,
+            traveled: 0.0,
+
+        };
+
+    }
+
+    pub fn start(&self) -> (f32, f32) {
+        return self.path[0];
+
+    }
+
+    pub fn end(&self) -> (f32, f32) {
+        return *self.path.last().unwrap();
+
+    }
+
+    pub fn finished(&self) -> bool {
+        return self.segment + 1 >= self.path.len();
+
+    }
+
+    // Advances the animation by `distance` and returns the new position of the piece
+    pub fn step(&mut self, mut distance: f32) -> (f32, f32) {
+        while !self.finished() {
+            let from = self.path[self.segment];
+            let to = self.path[self.segment + 1];
+            let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+
+            if self.traveled + distance < length {
+                self.traveled += distance;
+                let t = self.traveled / length;
+
+                return (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+
+            }
+
+            distance -= length - self.traveled;
+            self.traveled = 0.0;
+            self.segment += 1;
+
+        }
+
+        return self.end();
+
+    }
+
+}
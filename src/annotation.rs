@@ -0,0 +1,253 @@
+use macroquad::prelude::*;
+
+use crate::notation::{notation_to_square, square_to_notation};
+
+// Constants
+// Letters used for the annotation colors in game records: green, red, blue and yellow
+// The colors they are drawn in come from the theme
+pub const ANNOTATION_COLORS: [char; 4] = ['G', 'R', 'B', 'Y'];
+
+// Symbols that can be attached to a move, written after it in notation
+pub const MOVE_SYMBOLS: [&str; 6] = ["!!", "!", "!?", "?!", "?", "??"];
+
+// A mark drawn on the board by the user
+// Colors are indexes into `ANNOTATION_COLORS` and `Theme::annotations`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Annotation {
+    Arrow { from: usize, to: usize, color: usize },
+    Circle { square: usize, color: usize },
+
+}
+
+impl Annotation {
+    pub fn color(&self, colors: &[Color; 4]) -> Color {
+        let color = match self {
+            Annotation::Arrow { color, .. } => *color,
+            Annotation::Circle { color, .. } => *color,
+
+        };
+
+        return colors[color % colors.len()];
+
+    }
+
+    // Whether both annotations mark the same squares, ignoring the color
+    fn same_squares(&self, other: &Annotation) -> bool {
+        return match (self, other) {
+            (Annotation::Arrow { from, to, .. }, Annotation::Arrow { from: other_from, to: other_to, .. }) => from == other_from && to == other_to,
+            (Annotation::Circle { square, .. }, Annotation::Circle { square: other_square, .. }) => square == other_square,
+            _ => false,
+
+        };
+
+    }
+
+}
+
+// Everything the user added to a position in the history
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryNote {
+    pub comment: String,
+    pub symbol: Option<usize>, // Index into `MOVE_SYMBOLS`, for the move that led to the position
+    pub annotations: Vec<Annotation>,
+
+}
+
+impl HistoryNote {
+    pub fn new() -> HistoryNote {
+        return HistoryNote {
+            comment: String::new(),
+            symbol: None,
+            annotations: vec![],
+
+        };
+
+    }
+
+    pub fn symbol_str(&self) -> &str {
+        return self.symbol.map_or("", |symbol| MOVE_SYMBOLS[symbol]);
+
+    }
+
+    // The comment and annotations as the text of a record comment, empty if there is nothing to write
+    pub fn record_comment(&self) -> String {
+        // Braces would end the comment early and `[%` would start an annotation command
+        let comment = self.comment.trim().replace('{', "(").replace('}', ")").replace("[%", "[ %");
+
+        let annotations = annotations_str(&self.annotations);
+        if annotations.is_empty() || comment.is_empty() {
+            return format!("{}{}", annotations, comment);
+
+        }
+
+        return format!("{} {}", annotations, comment);
+
+    }
+
+}
+
+// Splits a move with a symbol like `b1-b3!?` into the move and the symbol
+pub fn split_symbol(token: &str) -> (&str, Option<usize>) {
+    let move_str = token.trim_end_matches(['!', '?']);
+    let symbol = MOVE_SYMBOLS.iter().position(|symbol| *symbol == &token[move_str.len()..]);
+
+    return (move_str, symbol);
+
+}
+
+// The free text of a record comment, without the annotation commands
+pub fn comment_text(data: &str) -> String {
+    let mut text = String::new();
+
+    let mut rest = data;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        rest = match rest[start..].find(']') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+
+        };
+
+    }
+    text.push_str(rest);
+
+    return text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+}
+
+// The color picked with the held modifier keys: none for green, Shift for red, Alt for blue and Ctrl for yellow
+pub fn held_color() -> usize {
+    if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+        return 1;
+
+    } else if is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt) {
+        return 2;
+
+    } else if is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl) {
+        return 3;
+
+    }
+
+    return 0;
+
+}
+
+// Adds an annotation, drawing the same one again removes it and drawing it in another color recolors it
+pub fn toggle_annotation(annotations: &mut Vec<Annotation>, annotation: Annotation) {
+    if let Some(idx) = annotations.iter().position(|other| other.same_squares(&annotation)) {
+        let existing = annotations.remove(idx);
+        if existing == annotation {
+            return;
+
+        }
+
+    }
+
+    annotations.push(annotation);
+
+}
+
+// Annotations as record commands: `[%csl Ga1,Rb2][%cal Gb1b3]`
+pub fn annotations_str(annotations: &[Annotation]) -> String {
+    let mut circles = vec![];
+    let mut arrows = vec![];
+    for annotation in annotations.iter() {
+        match annotation {
+            Annotation::Circle { square, color } => {
+                circles.push(format!("{}{}", ANNOTATION_COLORS[*color], square_to_notation(*square)));
+
+            },
+            Annotation::Arrow { from, to, color } => {
+                arrows.push(format!("{}{}{}", ANNOTATION_COLORS[*color], square_to_notation(*from), square_to_notation(*to)));
+
+            },
+
+        }
+
+    }
+
+    let mut data = String::new();
+    if !circles.is_empty() {
+        data.push_str(&format!("[%csl {}]", circles.join(",")));
+
+    }
+    if !arrows.is_empty() {
+        data.push_str(&format!("[%cal {}]", arrows.join(",")));
+
+    }
+
+    return data;
+
+}
+
+// Reads the annotation commands from a comment, ignoring any other text
+pub fn parse_annotations(data: &str) -> Result<Vec<Annotation>, String> {
+    let mut annotations = vec![];
+
+    let mut rest = data;
+    while let Some(start) = rest.find("[%") {
+        let end = rest[start..].find(']').ok_or(format!("Unclosed command: {}", &rest[start..]))? + start;
+        let (command, values) = rest[start + 2..end].trim().split_once(' ').unwrap_or((&rest[start + 2..end], ""));
+
+        for value in values.split(',').map(|value| value.trim()).filter(|value| !value.is_empty()) {
+            let annotation = parse_annotation(command, value).ok_or(format!("Invalid annotation: {}", value))?;
+            annotations.push(annotation);
+
+        }
+
+        rest = &rest[end + 1..];
+
+    }
+
+    return Ok(annotations);
+
+}
+
+// A single `Ga1` circle or `Gb1b3` arrow
+fn parse_annotation(command: &str, value: &str) -> Option<Annotation> {
+    let mut chars = value.chars();
+    let color_char = chars.next()?;
+    let color = ANNOTATION_COLORS.iter().position(|c| *c == color_char.to_ascii_uppercase())?;
+
+    // Every square is written with two characters
+    let squares: Vec<char> = chars.collect();
+    let squares: Vec<usize> = squares.chunks(2).map(|square| notation_to_square(&square.iter().collect::<String>())).collect::<Option<Vec<usize>>>()?;
+
+    return match (command, squares.as_slice()) {
+        ("csl", [square]) => Some(Annotation::Circle { square: *square, color }),
+        ("cal", [from, to]) => Some(Annotation::Arrow { from: *from, to: *to, color }),
+        _ => None,
+
+    };
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_comment_round_trip() {
+        let note = HistoryNote {
+            comment: "Wins {soon} [%csl Ga1]".to_string(),
+            symbol: None,
+            annotations: vec![Annotation::Circle { square: 0, color: 1 }, Annotation::Arrow { from: 1, to: 13, color: 0 }],
+
+        };
+
+        let comment = note.record_comment();
+        assert_eq!(comment, "[%csl Ra1][%cal Gb1b3] Wins (soon) [ %csl Ga1]");
+        assert_eq!(parse_annotations(&comment), Ok(note.annotations.clone()));
+        assert_eq!(comment_text(&comment), "Wins (soon) [ %csl Ga1]");
+
+    }
+
+    #[test]
+    fn symbols_are_split_from_moves() {
+        assert_eq!(split_symbol("b1-b3!?"), ("b1-b3", Some(2)));
+        assert_eq!(split_symbol("b1-b3"), ("b1-b3", None));
+        assert_eq!(split_symbol("b1-b3!!!"), ("b1-b3", None));
+
+    }
+
+}
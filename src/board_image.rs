@@ -0,0 +1,392 @@
+use macroquad::prelude::*;
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::annotation::{Annotation, HistoryNote};
+use crate::rules::move_polyline;
+use crate::theme::{pip_offsets, PieceStyle, Theme};
+use crate::{square_pos, BoardState, Move, BOARD_WIDTH, PIECE_RADIUS};
+
+// Constants
+pub const IMAGE_PATH: &str = "./board.png";
+pub const DEFAULT_IMAGE_SIZE: u16 = 900;
+pub const MAX_IMAGE_SIZE: u16 = 4096;
+pub const GIF_FRAME_DELAY: u16 = 100; // Time each position is shown in a game GIF, in hundredths of a second
+pub const GIF_QUANTIZE_SPEED: i32 = 10; // 1 to 30, lower values pick the 256 colors of a frame more carefully
+
+// Seven segment numerals, the start and end of every segment of a 12x18 digit centered on the piece
+pub const NUMERAL_SEGMENTS: [((f32, f32), (f32, f32)); 7] = [
+    ((-6.0, -9.0), (6.0, -9.0)), // Top
+    ((6.0, -9.0), (6.0, 0.0)), // Top right
+    ((6.0, 0.0), (6.0, 9.0)), // Bottom right
+    ((-6.0, 9.0), (6.0, 9.0)), // Bottom
+    ((-6.0, 0.0), (-6.0, 9.0)), // Bottom left
+    ((-6.0, -9.0), (-6.0, 0.0)), // Top left
+    ((-6.0, 0.0), (6.0, 0.0)), // Middle
+
+];
+
+// Board images are drawn on the CPU so they can be made without a window or graphics context
+// Shapes are given in board space (900x900) and sampled four times per pixel for smooth edges
+pub struct Canvas {
+    image: Image,
+    scale: f32,
+
+}
+
+impl Canvas {
+    pub fn new(size: u16, background: Color) -> Canvas {
+        let size = size.clamp(1, MAX_IMAGE_SIZE);
+        return Canvas {
+            image: Image::gen_image_color(size, size, background),
+            scale: size as f32 / BOARD_WIDTH,
+
+        };
+
+    }
+
+    // Blends a color into every pixel covered by `inside`, which is tested in board space
+    fn fill(&mut self, bounds: (f32, f32, f32, f32), color: Color, inside: impl Fn(f32, f32) -> bool) {
+        let width = self.image.width as usize;
+        let height = self.image.height as usize;
+
+        let x_start = ((bounds.0 * self.scale).floor().max(0.0) as usize).min(width);
+        let y_start = ((bounds.1 * self.scale).floor().max(0.0) as usize).min(height);
+        let x_end = ((bounds.2 * self.scale).ceil().max(0.0) as usize).min(width);
+        let y_end = ((bounds.3 * self.scale).ceil().max(0.0) as usize).min(height);
+
+        for py in y_start..y_end {
+            for px in x_start..x_end {
+                let mut samples = 0;
+                for (sx, sy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                    if inside((px as f32 + sx) / self.scale, (py as f32 + sy) / self.scale) {
+                        samples += 1;
+
+                    }
+
+                }
+
+                if samples > 0 {
+                    self.blend(px, py, color, color.a * samples as f32 / 4.0);
+
+                }
+
+            }
+
+        }
+
+    }
+
+    fn blend(&mut self, px: usize, py: usize, color: Color, alpha: f32) {
+        // `Image::export_png` expects the rows from the bottom up, like a texture read back from the GPU
+        let row = self.image.height as usize - 1 - py;
+        let i = (row * self.image.width as usize + px) * 4;
+
+        let channels = [color.r, color.g, color.b];
+        for (c, channel) in channels.iter().enumerate() {
+            let old = self.image.bytes[i + c] as f32 / 255.0;
+            self.image.bytes[i + c] = ((old * (1.0 - alpha) + channel * alpha) * 255.0).round() as u8;
+
+        }
+        self.image.bytes[i + 3] = 255;
+
+    }
+
+    // ========= Shapes =========
+
+    pub fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.fill((x, y, x + w, y + h), color, |px, py| px >= x && px <= x + w && py >= y && py <= y + h);
+
+    }
+
+    pub fn circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
+        self.fill((x - r, y - r, x + r, y + r), color, |px, py| (px - x).powi(2) + (py - y).powi(2) <= r * r);
+
+    }
+
+    pub fn circle_lines(&mut self, x: f32, y: f32, r: f32, thickness: f32, color: Color) {
+        let outer = r + thickness / 2.0;
+        self.fill((x - outer, y - outer, x + outer, y + outer), color, |px, py| {
+            let distance = ((px - x).powi(2) + (py - y).powi(2)).sqrt();
+            (distance - r).abs() <= thickness / 2.0
+
+        });
+
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
+        let half = thickness / 2.0;
+        let bounds = (x1.min(x2) - half, y1.min(y2) - half, x1.max(x2) + half, y1.max(y2) + half);
+
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length_sq = (dx * dx + dy * dy).max(f32::EPSILON);
+        self.fill(bounds, color, |px, py| {
+            let t = (((px - x1) * dx + (py - y1) * dy) / length_sq).clamp(0.0, 1.0);
+            (px - (x1 + t * dx)).powi(2) + (py - (y1 + t * dy)).powi(2) <= half * half
+
+        });
+
+    }
+
+    // Square standing on one corner, like the board outline
+    pub fn diamond(&mut self, x: f32, y: f32, r: f32, color: Color) {
+        self.fill((x - r, y - r, x + r, y + r), color, |px, py| (px - x).abs() + (py - y).abs() <= r);
+
+    }
+
+    // ========= Shapes =========
+
+    pub fn image(self) -> Image {
+        return self.image;
+
+    }
+
+}
+
+// Draws a position the same way the board is drawn on screen, without the text
+// `arrows` are moves drawn along their full path in the position before them
+pub fn board_image(boardstate: &BoardState, note: Option<&HistoryNote>, arrows: &[(Move, BoardState, Color)], size: u16, theme: &Theme, style: PieceStyle) -> Image {
+    let mut canvas = Canvas::new(size, theme.background);
+
+    // Board with rounded corners
+    canvas.diamond(450.0, 450.0, 450.0, theme.board);
+    canvas.rectangle(875.0, 0.0, 25.0, 900.0, theme.background);
+    canvas.rectangle(0.0, 875.0, 900.0, 25.0, theme.background);
+    canvas.rectangle(0.0, 0.0, 25.0, 900.0, theme.background);
+    canvas.rectangle(0.0, 0.0, 900.0, 25.0, theme.background);
+    canvas.circle(850.0, 450.0, 35.355, theme.board);
+    canvas.circle(450.0, 850.0, 35.355, theme.board);
+    canvas.circle(50.0, 450.0, 35.355, theme.board);
+    canvas.circle(450.0, 50.0, 35.355, theme.board);
+
+    // Gridspots
+    for i in 0..38 {
+        let pos = square_pos(i);
+        canvas.circle(pos.0, pos.1, 30.0, theme.gridspot);
+
+    }
+
+    for (i, piece) in boardstate.iter().enumerate() {
+        if *piece != 0 {
+            draw_piece(&mut canvas, square_pos(i), *piece, theme, style);
+
+        }
+
+    }
+
+    if let Some(note) = note {
+        for annotation in note.annotations.iter() {
+            match annotation {
+                Annotation::Circle { square, .. } => {
+                    let pos = square_pos(*square);
+                    canvas.circle_lines(pos.0, pos.1, PIECE_RADIUS + 5.0, 4.0, annotation.color(&theme.annotations));
+
+                },
+                Annotation::Arrow { from, to, .. } => {
+                    draw_arrow(&mut canvas, *from, *to, annotation.color(&theme.annotations));
+
+                },
+
+            }
+
+        }
+
+    }
+
+    for (mv, before, color) in arrows.iter() {
+        let polyline = move_polyline(before, mv);
+        for i in 1..polyline.len() {
+            draw_arrow(&mut canvas, polyline[i - 1], polyline[i], *color);
+
+        }
+
+    }
+
+    return canvas.image();
+
+}
+
+// Same as `Piece::draw`, with the numerals drawn as seven segment digits since there are no fonts here
+fn draw_piece(canvas: &mut Canvas, pos: (f32, f32), piece_type: usize, theme: &Theme, style: PieceStyle) {
+    let rings = if style == PieceStyle::Rings { piece_type } else { 1 };
+    for ring in 0..rings {
+        canvas.circle(pos.0, pos.1, PIECE_RADIUS - (ring as f32 * 10.0), theme.piece);
+        canvas.circle(pos.0, pos.1, PIECE_RADIUS - (ring as f32 * 10.0) - 5.0, theme.piece_detail);
+
+    }
+
+    if style == PieceStyle::Numerals {
+        let segments: &[usize] = match piece_type {
+            1 => &[1, 2],
+            2 => &[0, 1, 6, 4, 3],
+            3 => &[0, 1, 6, 2, 3],
+            _ => &[],
+
+        };
+
+        for segment in segments.iter() {
+            let (start, end) = NUMERAL_SEGMENTS[*segment];
+            canvas.line(pos.0 + start.0, pos.1 + start.1, pos.0 + end.0, pos.1 + end.1, 3.0, theme.piece);
+
+        }
+
+    } else if style == PieceStyle::Pips {
+        for offset in pip_offsets(piece_type) {
+            canvas.circle(pos.0 + offset.0, pos.1 + offset.1, 5.0, theme.piece);
+
+        }
+
+    }
+
+}
+
+// Same style as the arrows on the board: a line with a dot at the end
+fn draw_arrow(canvas: &mut Canvas, from: usize, to: usize, color: Color) {
+    let pos_1 = square_pos(from);
+    let pos_2 = square_pos(to);
+
+    canvas.line(pos_1.0, pos_1.1, pos_2.0, pos_2.1, 2.5, color);
+    canvas.circle(pos_2.0, pos_2.1, 5.0, color);
+
+}
+
+// Reads an image width in pixels
+pub fn parse_image_size(data: &str) -> Result<u16, String> {
+    return match data.trim().parse::<u16>() {
+        Ok(size) if size > 0 && size <= MAX_IMAGE_SIZE => Ok(size),
+        _ => Err(format!("Image size must be between 1 and {}: {}", MAX_IMAGE_SIZE, data.trim())),
+
+    };
+
+}
+
+// Writes an image as a PNG, creating the directory if needed
+pub fn save_png(image: &Image, path: &str) -> Result<(), String> {
+    if !path.to_lowercase().ends_with(".png") {
+        return Err(format!("Image path must end with .png: {}", path));
+
+    }
+
+    if let Some(dir) = Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+
+    }
+
+    // `export_png` panics on errors, so make sure the file can be written first
+    fs::File::create(path).map_err(|err| format!("Can't write {}: {}", path, err))?;
+    image.export_png(path);
+
+    return Ok(());
+
+}
+
+// Writes every position of a game, each with its annotations and the move that led to it
+// A path ending in .gif is written as an animated GIF, any other path as one image per position
+// named `<path>_000.png`, `<path>_001.png`, ...
+// Returns the number of positions written
+pub fn export_game(history: &[(BoardState, Move)], notes: &[HistoryNote], path: &str, size: u16, theme: &Theme, style: PieceStyle) -> Result<usize, String> {
+    let frames = history.iter().enumerate().map(|(i, (boardstate, mv))| {
+        let arrows = if i > 0 { vec![(mv.clone(), history[i - 1].0, theme.history_move)] } else { vec![] };
+        board_image(boardstate, notes.get(i), &arrows, size, theme, style)
+
+    });
+
+    if path.to_lowercase().ends_with(".gif") {
+        save_gif(frames, size, path)?;
+
+    } else {
+        let prefix = path.strip_suffix(".png").unwrap_or(path);
+        for (i, image) in frames.enumerate() {
+            save_png(&image, &format!("{}_{:03}.png", prefix, i))?;
+
+        }
+
+    }
+
+    return Ok(history.len());
+
+}
+
+// Writes square images of the given size as the frames of an animated GIF that loops forever
+pub fn save_gif(frames: impl Iterator<Item = Image>, size: u16, path: &str) -> Result<(), String> {
+    if let Some(dir) = Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+
+    }
+
+    let file = fs::File::create(path).map_err(|err| format!("Can't write {}: {}", path, err))?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), size, size, &[]).map_err(|err| err.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|err| err.to_string())?;
+
+    for image in frames {
+        // `Image` keeps the rows from the bottom up and GIFs store them from the top down
+        let mut pixels: Vec<u8> = image.bytes.chunks(image.width as usize * 4).rev().flatten().copied().collect();
+        let mut frame = gif::Frame::from_rgba_speed(image.width, image.height, &mut pixels, GIF_QUANTIZE_SPEED);
+        frame.delay = GIF_FRAME_DELAY;
+        encoder.write_frame(&frame).map_err(|err| format!("Can't write {}: {}", path, err))?;
+
+    }
+
+    return Ok(());
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::apply_move;
+
+    // Decodes a GIF written by `save_gif` into the RGBA pixels of its frames
+    fn read_gif(path: &str) -> (u16, Vec<Vec<u8>>) {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(fs::File::open(path).unwrap()).unwrap();
+
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push(frame.buffer.to_vec());
+
+        }
+
+        return (decoder.width(), frames);
+
+    }
+
+    #[test]
+    fn gif_rows_start_at_the_top() {
+        let mut canvas = Canvas::new(20, WHITE);
+        canvas.rectangle(0.0, 0.0, 900.0, 450.0, BLACK);
+
+        let path = std::env::temp_dir().join("gyges_rows_test.gif");
+        let path = path.to_str().unwrap();
+        save_gif(vec![canvas.image()].into_iter(), 20, path).unwrap();
+
+        let (size, frames) = read_gif(path);
+        let _ = fs::remove_file(path);
+
+        assert_eq!((size, frames.len()), (20, 1));
+        assert_eq!(frames[0][..4], [0, 0, 0, 255]);
+        assert_eq!(frames[0][frames[0].len() - 4..], [255, 255, 255, 255]);
+
+    }
+
+    #[test]
+    fn game_gif_has_a_frame_per_position() {
+        let mut boardstate = [0; 38];
+        boardstate[0] = 1;
+        let history = vec![(boardstate, vec![]), (apply_move(&boardstate, &vec![0, 6]), vec![0, 6])];
+
+        let path = std::env::temp_dir().join("gyges_game_test.gif");
+        let path = path.to_str().unwrap();
+        assert_eq!(export_game(&history, &[], path, 30, &Theme::light(), PieceStyle::Rings), Ok(2));
+
+        let (size, frames) = read_gif(path);
+        let _ = fs::remove_file(path);
+
+        assert_eq!((size, frames.len()), (30, 2));
+
+    }
+
+}
@@ -0,0 +1,91 @@
+use crate::board_image::{board_image, export_game, parse_image_size, save_png, DEFAULT_IMAGE_SIZE};
+use crate::game_record::GameRecord;
+use crate::settings::UserSettings;
+use crate::theme::{load_themes, Theme, PIECE_STYLES};
+
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+// Constants
+pub const USAGE: &str = "Usage:
+  gygesUI                                              Start the UI
+  gygesUI --export-image <record> <image.png> [size]   Save the last position of a game record as an image
+  gygesUI --export-game <record> <prefix> [size]       Save every position of a game record as <prefix>_000.png, <prefix>_001.png, ...
+  gygesUI --export-game <record> <game.gif> [size]     Save every position of a game record as an animated GIF
+  gygesUI --help                                       Show this message";
+
+// Runs a command line export without opening a window, returns the exit code
+pub fn run(args: &[String]) -> i32 {
+    // The executable uses the Windows GUI subsystem and starts without a console,
+    // so messages are printed to the console of the shell that ran it
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+
+    return match run_command(args) {
+        Ok(message) => {
+            println!("{}", message);
+            0
+
+        },
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+
+        },
+
+    };
+
+}
+
+fn run_command(args: &[String]) -> Result<String, String> {
+    let command = args.first().map(|arg| arg.as_str()).unwrap_or("");
+    if command == "--help" || command == "-h" {
+        return Ok(USAGE.to_string());
+
+    }
+
+    if command != "--export-image" && command != "--export-game" {
+        return Err(format!("Unknown argument: {}\n\n{}", command, USAGE));
+
+    }
+
+    let (Some(record_path), Some(image_path)) = (args.get(1), args.get(2)) else {
+        return Err(format!("Missing arguments for {}\n\n{}", command, USAGE));
+
+    };
+    if args.len() > 4 {
+        return Err(format!("Too many arguments for {}\n\n{}", command, USAGE));
+
+    }
+
+    let size = match args.get(3) {
+        Some(size) => parse_image_size(size)?,
+        None => DEFAULT_IMAGE_SIZE,
+
+    };
+
+    // Images look like the board in the UI
+    let settings = UserSettings::load();
+    let (themes, _) = load_themes();
+    let theme = themes.into_iter().find(|theme| theme.name == settings.theme).unwrap_or(Theme::light());
+    let theme = if settings.colorblind_arrows { theme.with_colorblind_arrows() } else { theme };
+    let style = PIECE_STYLES[settings.piece_style.min(PIECE_STYLES.len() - 1)];
+
+    let record = GameRecord::load(record_path).map_err(|err| format!("Can't load {}: {}", record_path, err))?;
+    let history = record.to_history();
+    let notes = record.position_notes();
+
+    if command == "--export-image" {
+        // Like the last image of `--export-game`, with the move that led to the position
+        let last = history.len() - 1;
+        let arrows = if last > 0 { vec![(history[last].1.clone(), history[last - 1].0, theme.history_move)] } else { vec![] };
+        let image = board_image(&history[last].0, notes.get(last), &arrows, size, &theme, style);
+        save_png(&image, image_path)?;
+
+        return Ok(format!("Exported {}", image_path));
+
+    }
+
+    let count = export_game(&history, &notes, image_path, size, &theme, style)?;
+
+    return Ok(format!("Exported {} positions", count));
+
+}
@@ -0,0 +1,234 @@
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+
+// The kind of time control used by the `GameClock`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    None,
+    SuddenDeath { base: f32 },
+    Increment { base: f32, increment: f32 },
+    MovesPerPeriod { base: f32, moves: usize },
+
+}
+
+// Time information forwarded to the engine with `go`
+// Times are in seconds and ordered as (P1, P2)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockTimes {
+    pub remaining: (f32, f32),
+    pub increment: f32,
+    pub moves_to_go: Option<usize>,
+
+}
+
+// Chess style game clock for both players
+// Index 0 of the arrays is P1 and index 1 is P2
+pub struct GameClock {
+    pub control: TimeControl,
+
+    remaining: [f32; 2],
+    moves_made: [usize; 2],
+
+    to_move: usize,
+    running: bool,
+
+    flagged: Option<usize>,
+
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> GameClock {
+        let mut clock = GameClock {
+            control,
+
+            remaining: [0.0, 0.0],
+            moves_made: [0, 0],
+
+            to_move: 0,
+            running: false,
+
+            flagged: None,
+
+        };
+        clock.reset(0);
+
+        return clock;
+
+    }
+
+    // ========= Helper Functions =========
+
+    pub fn enabled(&self) -> bool {
+        return self.control != TimeControl::None;
+
+    }
+
+    pub fn times(&self) -> Option<ClockTimes> {
+        let (increment, moves_to_go) = match self.control {
+            TimeControl::None => { return None },
+            TimeControl::SuddenDeath { .. } => (0.0, None),
+            TimeControl::Increment { increment, .. } => (increment, None),
+            TimeControl::MovesPerPeriod { moves, .. } => (0.0, Some(moves - (self.moves_made[self.to_move] % moves))),
+
+        };
+
+        return Some(ClockTimes {
+            remaining: (self.remaining[0], self.remaining[1]),
+            increment,
+            moves_to_go,
+
+        });
+
+    }
+
+    // The result of the game if a flag has fallen
+    pub fn result(&self) -> Option<String> {
+        return match self.flagged {
+            Some(0) => Some("P2 wins on time".to_string()),
+            Some(_) => Some("P1 wins on time".to_string()),
+            None => None,
+
+        };
+
+    }
+
+    // The player who won on time, 1.0 for P1 and -1.0 for P2
+    pub fn winner(&self) -> Option<f64> {
+        return self.flagged.map(|side| if side == 0 { -1.0 } else { 1.0 });
+
+    }
+
+    fn base(&self) -> f32 {
+        return match self.control {
+            TimeControl::None => 0.0,
+            TimeControl::SuddenDeath { base } => base,
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::MovesPerPeriod { base, .. } => base,
+
+        };
+
+    }
+
+    // ========= Helper Functions =========
+
+    // Resets both clocks to the base time with `to_move` on move
+    pub fn reset(&mut self, to_move: usize) {
+        let base = self.base();
+
+        self.remaining = [base, base];
+        self.moves_made = [0, 0];
+        self.to_move = to_move;
+        self.running = false;
+        self.flagged = None;
+
+    }
+
+    // Gives the move to a player without touching the times, `side` is 1.0 for P1 and -1.0 for P2
+    // Used when the engine is told which side to play, so the running clock is always the engine's
+    pub fn set_side_to_move(&mut self, side: f64) {
+        self.to_move = if side == 1.0 { 0 } else { 1 };
+
+    }
+
+    pub fn start(&mut self) {
+        if self.enabled() && self.flagged.is_none() {
+            self.running = true;
+
+        }
+
+    }
+
+    pub fn pause(&mut self) {
+        self.running = false;
+
+    }
+
+    // Ends the current players turn and starts the opponents clock
+    pub fn press(&mut self) {
+        if !self.running {
+            return;
+
+        }
+
+        let side = self.to_move;
+        self.moves_made[side] += 1;
+
+        match self.control {
+            TimeControl::Increment { increment, .. } => {
+                self.remaining[side] += increment;
+
+            },
+            TimeControl::MovesPerPeriod { base, moves } if self.moves_made[side].is_multiple_of(moves) => {
+                self.remaining[side] += base;
+
+            },
+            _ => {}
+
+        }
+
+        self.to_move = 1 - side;
+
+    }
+
+    // Main update function
+    // Returns true if a flag fell this frame
+    pub fn update(&mut self, dt: f32) -> bool {
+        if !self.running {
+            return false;
+
+        }
+
+        self.remaining[self.to_move] -= dt;
+        if self.remaining[self.to_move] <= 0.0 {
+            self.remaining[self.to_move] = 0.0;
+            self.flagged = Some(self.to_move);
+            self.running = false;
+
+            return true;
+
+        }
+
+        return false;
+
+    }
+
+    // Main render function
+    // Draws the P1 clock at `p1_pos` and the P2 clock at `p2_pos`
+    pub fn render(&self, theme: &Theme, p1_pos: (f32, f32), p2_pos: (f32, f32)) {
+        if !self.enabled() {
+            return;
+
+        }
+
+        for (side, pos) in [(0, p1_pos), (1, p2_pos)] {
+            let text = format_clock(self.remaining[side]);
+            let text_size = measure_text(&text, None, 40, 1.0);
+
+            let color = if self.flagged == Some(side) { RED } else { theme.text };
+            draw_text_ex(&text, pos.0 - (text_size.width / 2.0), pos.1 + (text_size.height / 2.0), TextParams { font_size: 40, color, ..Default::default() });
+
+            if self.to_move == side && (self.running || self.flagged == Some(side)) {
+                let box_color = if self.running { theme.accent } else { RED };
+                draw_rectangle_lines(pos.0 - (text_size.width / 2.0) - 10.0, pos.1 - 25.0, text_size.width + 20.0, 50.0, 4.0, box_color);
+
+            }
+
+        }
+
+    }
+
+}
+
+// Formats seconds as m:ss, with tenths below ten seconds
+pub fn format_clock(seconds: f32) -> String {
+    let seconds = seconds.max(0.0);
+    if seconds < 10.0 {
+        return format!("0:{:04.1}", seconds);
+
+    }
+
+    let total = seconds.floor() as u32;
+    return format!("{}:{:02}", total / 60, total % 60);
+
+}
@@ -0,0 +1,134 @@
+use macroquad::prelude::*;
+
+use crate::ugi_engine::UgiEngine;
+use crate::theme::Theme;
+
+// Constants
+pub const EVAL_BAR_WIDTH: f32 = 15.0;
+pub const EVAL_BAR_GAP: f32 = 5.0; // Space between the board and the bar
+pub const EVAL_SCALE: f64 = 1000.0; // Score at which the bar is ~88% filled
+pub const WIN_SCORE: f64 = 100000.0; // Scores at or above this are forced wins
+pub const EVAL_ANIM_SPEED: f32 = 6.0; // Fraction of the gap closed per second
+
+pub const COLOR_EVAL_P1: Color = Color::new(0.95, 0.95, 0.95, 1.0);
+pub const COLOR_EVAL_P2: Color = Color::new(0.15, 0.15, 0.15, 1.0);
+
+// Vertical evaluation bar drawn beside the board
+// The bottom half belongs to P1 and the top half to P2, or the other way around on a flipped board
+pub struct EvalBar {
+    pos: (f32, f32),
+    height: f32,
+
+    target: f32,
+    shown: f32,
+
+    score: Option<f64>,
+
+}
+
+impl EvalBar {
+    pub fn new(x: f32, y: f32, height: f32) -> EvalBar {
+        return EvalBar {
+            pos: (x, y),
+            height,
+
+            target: 0.5,
+            shown: 0.5,
+
+            score: None,
+
+        };
+
+    }
+
+    // Converts an engine score into P1's perspective
+    // The engine always reports scores for the side it is searching for
+    pub fn p1_score(score: f64, side: f64) -> f64 {
+        return score * side;
+
+    }
+
+    // Maps a P1 score to the fraction of the bar filled by P1
+    pub fn fill_fraction(p1_score: f64) -> f32 {
+        if p1_score >= WIN_SCORE {
+            return 1.0;
+
+        } else if p1_score <= -WIN_SCORE {
+            return 0.0;
+
+        }
+
+        return (0.5 + 0.5 * (p1_score / EVAL_SCALE).tanh()) as f32;
+
+    }
+
+    // Main update function
+    pub fn update(&mut self, engine: &UgiEngine) {
+        match engine.best_search.score {
+            Some(score) => {
+                let p1_score = EvalBar::p1_score(score, engine.side);
+                self.score = Some(p1_score);
+                self.target = EvalBar::fill_fraction(p1_score);
+
+            },
+            None => {
+                self.score = None;
+                self.target = 0.5;
+
+            }
+
+        }
+
+        let step = (EVAL_ANIM_SPEED * get_frame_time()).min(1.0);
+        self.shown += (self.target - self.shown) * step;
+
+    }
+
+    // Main render function
+    pub fn render(&self, theme: &Theme, flipped: bool) {
+        let (x, y) = self.pos;
+        let p1_height = self.height * self.shown;
+
+        draw_rectangle(x, y, EVAL_BAR_WIDTH, self.height, COLOR_EVAL_P2);
+        let p1_y = if flipped { y } else { y + self.height - p1_height };
+        draw_rectangle(x, p1_y, EVAL_BAR_WIDTH, p1_height, COLOR_EVAL_P1);
+        draw_line(x, y + self.height / 2.0, x + EVAL_BAR_WIDTH, y + self.height / 2.0, 1.0, theme.board);
+
+        // Forced wins are outlined in the accent color and marked with W/L on the winning side
+        if let Some(score) = self.score {
+            if score.abs() >= WIN_SCORE {
+                let bottom_wins = (score > 0.0) != flipped;
+                let color = if score > 0.0 { COLOR_EVAL_P2 } else { COLOR_EVAL_P1 };
+                let (win_y, text_y) = if bottom_wins { 
+                    (y + self.height / 2.0, y + self.height - 6.0)
+
+                } else {
+                    (y, y + 18.0)
+
+                };
+                draw_rectangle_lines(x, win_y, EVAL_BAR_WIDTH, self.height / 2.0, 3.0, theme.accent);
+
+                let text = EvalBar::score_text(score);
+                let text_size = measure_text(&text, None, 16, 1.0);
+                draw_text_ex(&text, x + (EVAL_BAR_WIDTH - text_size.width) / 2.0, text_y, TextParams { font_size: 16, color, ..Default::default() });
+
+            }
+
+        }
+
+        draw_rectangle_lines(x, y, EVAL_BAR_WIDTH, self.height, 1.0, theme.text);
+
+    }
+
+    // Short label for a forced win score
+    pub fn score_text(p1_score: f64) -> String {
+        if p1_score > 0.0 {
+            return "W".to_string();
+
+        }
+
+        return "L".to_string();
+
+    }
+
+}
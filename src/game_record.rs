@@ -0,0 +1,312 @@
+use std::fs;
+
+use crate::annotation::{comment_text, parse_annotations, split_symbol, HistoryNote};
+use crate::notation::{move_to_notation, notation_to_move};
+use crate::rules::apply_move;
+use crate::{boardstate_to_str, BoardState, Move};
+
+// Constants
+pub const RECORD_PATH: &str = "./game.txt";
+
+// A saved game: the starting position, the moves in notation and the result
+//
+// [Start "32112300000000000000000000000032112300"]
+// [Result "P1 wins on time"]
+//
+// 1. b1-b3!? {[%cal Gc6c4] Threatens c4} c6-c4/a4 2. ...
+//
+// Comments in braces belong to the position after the move before them,
+// a comment before the first move belongs to the starting position.
+// They hold the annotation commands followed by the comment text.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub start: BoardState,
+    pub moves: Vec<Move>,
+    pub notes: Vec<HistoryNote>, // One for every position, starting with the starting position
+    pub result: Option<String>,
+
+}
+
+// Parts of the move text
+enum Token {
+    Word(String),
+    Comment(String),
+
+}
+
+impl GameRecord {
+    pub fn from_history(history: &[(BoardState, Move)], notes: &[HistoryNote], result: Option<String>) -> GameRecord {
+        return GameRecord {
+            start: history[0].0,
+            moves: history.iter().skip(1).map(|(_, mv)| mv.clone()).collect(),
+            notes: notes.to_vec(),
+            result,
+
+        };
+
+    }
+
+    // The notes of every position, padded to the length of the game
+    pub fn position_notes(&self) -> Vec<HistoryNote> {
+        let mut notes = self.notes.clone();
+        notes.resize(self.moves.len() + 1, HistoryNote::new());
+
+        return notes;
+
+    }
+
+    // Replays the moves from the starting position
+    pub fn to_history(&self) -> Vec<(BoardState, Move)> {
+        let mut history = vec![(self.start, vec![])];
+        for mv in self.moves.iter() {
+            let boardstate = apply_move(&history.last().unwrap().0, mv);
+            history.push((boardstate, mv.clone()));
+
+        }
+
+        return history;
+
+    }
+
+    pub fn record_str(&self) -> String {
+        let mut record = format!("[Start \"{}\"]\n", boardstate_to_str(&self.start));
+        if let Some(result) = &self.result {
+            record.push_str(&format!("[Result \"{}\"]\n", result));
+
+        }
+        record.push('\n');
+
+        let notes = self.position_notes();
+        let comment = notes[0].record_comment();
+        if !comment.is_empty() {
+            record.push_str(&format!("{{{}}} ", comment));
+
+        }
+
+        for (i, mv) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                record.push_str(&format!("{}. ", (i / 2) + 1));
+
+            }
+            record.push_str(&format!("{}{} ", move_to_notation(mv), notes[i + 1].symbol_str()));
+
+            let comment = notes[i + 1].record_comment();
+            if !comment.is_empty() {
+                record.push_str(&format!("{{{}}} ", comment));
+
+            }
+
+        }
+        record.push('\n');
+
+        return record;
+
+    }
+
+    pub fn parse(data: &str) -> Result<GameRecord, String> {
+        let mut start = None;
+        let mut result = None;
+        let mut moves = vec![];
+        let mut notes = vec![HistoryNote::new()];
+        let mut move_text = String::new();
+
+        for line in data.lines() {
+            let line = line.trim();
+            if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let (key, value) = tag.split_once(' ').ok_or(format!("Invalid tag: {}", line))?;
+                let value = value.trim().trim_matches('"');
+                match key {
+                    "Start" => { start = Some(parse_boardstate(value)?) },
+                    "Result" => { result = Some(value.to_string()) },
+                    _ => {}
+
+                }
+
+                continue;
+
+            }
+
+            move_text.push_str(line);
+            move_text.push(' ');
+
+        }
+
+        for token in tokenize(&move_text)? {
+            match token {
+                // Skip move numbers
+                Token::Word(word) if word.ends_with('.') => {},
+                Token::Word(word) => {
+                    let (move_str, symbol) = split_symbol(&word);
+                    moves.push(notation_to_move(move_str).ok_or(format!("Invalid move: {}", word))?);
+
+                    let mut note = HistoryNote::new();
+                    note.symbol = symbol;
+                    notes.push(note);
+
+                },
+                Token::Comment(comment) => {
+                    let note = notes.last_mut().unwrap();
+                    note.annotations.extend(parse_annotations(&comment)?);
+
+                    let text = comment_text(&comment);
+                    if !text.is_empty() {
+                        note.comment = if note.comment.is_empty() { text } else { format!("{} {}", note.comment, text) };
+
+                    }
+
+                },
+
+            }
+
+        }
+
+        return Ok(GameRecord {
+            start: start.ok_or("Missing start position".to_string())?,
+            moves,
+            notes,
+            result,
+
+        });
+
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        return fs::write(path, self.record_str()).map_err(|err| err.to_string());
+
+    }
+
+    pub fn load(path: &str) -> Result<GameRecord, String> {
+        let data = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+        return GameRecord::parse(&data);
+
+    }
+
+}
+
+// Splits the move text into words and comments in braces
+fn tokenize(data: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+
+    let mut rest = data.trim_start();
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix('{') {
+            let end = comment.find('}').ok_or("Unclosed comment".to_string())?;
+            tokens.push(Token::Comment(comment[..end].to_string()));
+            rest = &comment[end + 1..];
+
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+
+        }
+
+        rest = rest.trim_start();
+
+    }
+
+    return Ok(tokens);
+
+}
+
+pub fn parse_boardstate(data: &str) -> Result<BoardState, String> {
+    let mut boardstate = [0; 38];
+    if data.len() != 38 {
+        return Err(format!("Start position must have 38 squares, found {}", data.len()));
+
+    }
+
+    for (i, c) in data.chars().enumerate() {
+        match c.to_digit(10) {
+            Some(piece) if piece <= 3 => { boardstate[i] = piece as usize },
+            _ => { return Err(format!("Invalid piece: {}", c)) },
+
+        }
+
+    }
+
+    return Ok(boardstate);
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::annotation::Annotation;
+
+    const START: &str = "32112300000000000000000000000032112300";
+
+    #[test]
+    fn record_round_trips() {
+        let mut start_note = HistoryNote::new();
+        start_note.comment = "Mirrored rows".to_string();
+
+        let mut note = HistoryNote::new();
+        note.symbol = Some(2);
+        note.comment = "Threatens c4".to_string();
+        note.annotations = vec![Annotation::Circle { square: 14, color: 1 }, Annotation::Arrow { from: 32, to: 20, color: 0 }]; // Circles are written first
+
+        let mut replace_note = HistoryNote::new();
+        replace_note.symbol = Some(5);
+
+        let record = GameRecord {
+            start: parse_boardstate(START).unwrap(),
+            moves: vec![vec![1, 13], vec![32, 13, 24]],
+            notes: vec![start_note, note, replace_note],
+            result: Some("P1 wins on time".to_string()),
+
+        };
+
+        let parsed = GameRecord::parse(&record.record_str()).unwrap();
+        assert_eq!(parsed.start, record.start);
+        assert_eq!(parsed.moves, record.moves);
+        assert_eq!(parsed.notes, record.notes);
+        assert_eq!(parsed.result, record.result);
+
+    }
+
+    #[test]
+    fn parses_comments_symbols_and_annotations() {
+        let data = format!("[Start \"{}\"]\n\n1. b1-b3!? {{[%cal Gc6c4] Threatens c4}} c6-c4/a4 {{[%csl Ra4,Yb3]}}\n", START);
+        let record = GameRecord::parse(&data).unwrap();
+
+        assert_eq!(record.moves, vec![vec![1, 13], vec![32, 20, 18]]);
+        assert_eq!(record.result, None);
+
+        let notes = record.position_notes();
+        assert_eq!(notes[0], HistoryNote::new());
+        assert_eq!(notes[1].symbol_str(), "!?");
+        assert_eq!(notes[1].comment, "Threatens c4");
+        assert_eq!(notes[1].annotations, vec![Annotation::Arrow { from: 32, to: 20, color: 0 }]);
+        assert_eq!(notes[2].comment, "");
+        assert_eq!(notes[2].annotations, vec![Annotation::Circle { square: 18, color: 1 }, Annotation::Circle { square: 13, color: 3 }]);
+
+        let history = record.to_history();
+        assert_eq!(history.len(), 3);
+        assert_eq!((history[2].0[13], history[2].0[20], history[2].0[32]), (2, 1, 0));
+
+    }
+
+    #[test]
+    fn braces_in_comments_are_replaced() {
+        let mut note = HistoryNote::new();
+        note.comment = "see {this}".to_string();
+
+        let record = GameRecord { start: parse_boardstate(START).unwrap(), moves: vec![], notes: vec![note], result: None };
+        let parsed = GameRecord::parse(&record.record_str()).unwrap();
+        assert_eq!(parsed.notes[0].comment, "see (this)");
+
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        assert!(GameRecord::parse("1. b1-b3").is_err());
+        assert!(GameRecord::parse(&format!("[Start \"{}\"]\n1. b1-b9", START)).is_err());
+        assert!(GameRecord::parse(&format!("[Start \"{}\"]\n1. b1-b3 {{unclosed", START)).is_err());
+        assert!(parse_boardstate("3211").is_err());
+
+    }
+
+}
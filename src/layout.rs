@@ -0,0 +1,124 @@
+use macroquad::prelude::*;
+
+use crate::eval_bar::{EVAL_BAR_GAP, EVAL_BAR_WIDTH};
+use crate::{BOARD_HEIGHT, BOARD_WIDTH};
+
+// Constants
+pub const BOARD_AREA_WIDTH: f32 = BOARD_WIDTH + EVAL_BAR_GAP + EVAL_BAR_WIDTH; // The board and the eval bar beside it
+pub const PANEL_WIDTH: f32 = 555.0; // Screen space right of the eval bar used by the side windows
+pub const PANEL_GAP: f32 = 25.0;
+pub const CONTROLS_WIDTH: f32 = 250.0;
+pub const HISTORY_WIDTH: f32 = 250.0;
+pub const HISTORY_MOVE_X: f32 = 35.0; // Offset of the P1 move in a history row
+pub const HISTORY_MOVE_WIDTH: f32 = 100.0;
+pub const HISTORY_COMMENT_LENGTH: usize = 20; // Characters of a comment shown in the history list
+pub const NOTES_HEIGHT: f32 = 85.0;
+pub const LAYOUT_HEIGHT: f32 = 900.0; // Height the side window positions are designed for
+pub const MIN_SCALE: f32 = 0.2;
+
+// Screen layout of the board and the side windows
+// The board is drawn in its own 900x900 space and scaled to fit the window,
+// while the side windows keep their width and reflow vertically
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub scale: f32,
+    pub board_origin: (f32, f32),
+
+    pub panel_x: f32,
+    pub history_x: f32,
+    pub v_scale: f32,
+
+    pub screen: (f32, f32),
+
+}
+
+impl Layout {
+    pub fn new(screen_w: f32, screen_h: f32) -> Layout {
+        let scale = ((screen_w - PANEL_WIDTH) / BOARD_AREA_WIDTH).min(screen_h / BOARD_HEIGHT).max(MIN_SCALE);
+
+        let used_w = BOARD_AREA_WIDTH * scale + PANEL_WIDTH;
+        let origin_x = ((screen_w - used_w) / 2.0).max(0.0);
+        let origin_y = ((screen_h - BOARD_HEIGHT * scale) / 2.0).max(0.0);
+
+        // The eval bar scales with the board, so the side windows start after it at every scale
+        let panel_x = origin_x + BOARD_AREA_WIDTH * scale + EVAL_BAR_GAP;
+
+        return Layout {
+            scale,
+            board_origin: (origin_x, origin_y),
+
+            panel_x,
+            history_x: panel_x + CONTROLS_WIDTH + PANEL_GAP,
+            v_scale: screen_h / LAYOUT_HEIGHT,
+
+            screen: (screen_w, screen_h),
+
+        };
+
+    }
+
+    // Layout for the current window size
+    pub fn current() -> Layout {
+        return Layout::new(screen_width(), screen_height());
+
+    }
+
+    // ========= Conversions =========
+
+    pub fn screen_to_board(&self, point: (f32, f32)) -> (f32, f32) {
+        return ((point.0 - self.board_origin.0) / self.scale, (point.1 - self.board_origin.1) / self.scale);
+
+    }
+
+    pub fn board_to_screen(&self, point: (f32, f32)) -> Vec2 {
+        return vec2(point.0 * self.scale + self.board_origin.0, point.1 * self.scale + self.board_origin.1);
+
+    }
+
+    // Camera that maps board space onto the screen
+    pub fn board_camera(&self) -> Camera2D {
+        let view_w = self.screen.0 / self.scale;
+        let view_h = self.screen.1 / self.scale;
+        let view_x = -self.board_origin.0 / self.scale;
+        let view_y = -self.board_origin.1 / self.scale;
+
+        return Camera2D {
+            target: vec2(view_x + view_w / 2.0, view_y + view_h / 2.0),
+            zoom: vec2(2.0 / view_w, 2.0 / view_h),
+            ..Default::default()
+
+        };
+
+    }
+
+    // ========= Conversions =========
+
+    // ========= Windows =========
+
+    // Position and size of a side window given its position in the 900px tall design
+    pub fn controls_window(&self, y: f32, height: f32) -> (Vec2, Vec2) {
+        return (vec2(self.panel_x, y * self.v_scale), vec2(CONTROLS_WIDTH, height * self.v_scale));
+
+    }
+
+    pub fn history_controls_window(&self) -> (Vec2, Vec2) {
+        return (vec2(self.history_x, 50.0 * self.v_scale), vec2(HISTORY_WIDTH, 35.0));
+
+    }
+
+    pub fn history_window(&self) -> (Vec2, Vec2) {
+        let top = 50.0 * self.v_scale + 45.0;
+        let bottom = 850.0 * self.v_scale - NOTES_HEIGHT - 10.0;
+        return (vec2(self.history_x, top), vec2(HISTORY_WIDTH, bottom - top));
+
+    }
+
+    // Editor for the comment and symbol of the current position, below the history
+    pub fn history_notes_window(&self) -> (Vec2, Vec2) {
+        return (vec2(self.history_x, 850.0 * self.v_scale - NOTES_HEIGHT), vec2(HISTORY_WIDTH, NOTES_HEIGHT));
+
+    }
+
+    // ========= Windows =========
+
+}
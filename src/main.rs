@@ -1,11 +1,47 @@
 #![windows_subsystem = "windows"]
 
 mod ugi_engine;
+mod eval_bar;
+mod analysis_cache;
+mod search_log;
+mod clock;
+mod layout;
+mod animation;
+mod rules;
+mod notation;
+mod game_record;
+mod settings;
+mod ugi_log;
+mod openings;
+mod shortcuts;
+mod annotation;
+mod theme;
+mod board_image;
+mod cli;
 
 use macroquad::prelude::*;
 use macroquad::ui::{self, widgets, hash};
 
-use ugi_engine::{Mode, UgiEngine, MAX_PLY, MAX_TIME};
+use ugi_engine::{split_args, EngineConfig, Mode, UgiEngine, MAX_PLY, MAX_TIME};
+use eval_bar::{EvalBar, EVAL_BAR_GAP, WIN_SCORE};
+use analysis_cache::{AnalysisCache, CACHE_PATH};
+use search_log::SEARCH_LOG_PATH;
+use clock::{GameClock, TimeControl};
+use layout::{Layout, HISTORY_COMMENT_LENGTH, HISTORY_MOVE_WIDTH, HISTORY_MOVE_X};
+use animation::{PieceAnimation, ANIM_SPEEDS, FAST_SIM_TIME};
+use rules::{apply_move, goal_threats, is_legal_move, move_path, move_polyline};
+use notation::{move_to_notation, notation_to_move, square_to_notation};
+use game_record::{GameRecord, RECORD_PATH};
+use settings::UserSettings;
+use ugi_log::UGI_LOG_PATH;
+use openings::{arrangement_str, match_length, match_opening, parse_starting_board, random_arrangement, starting_board, MatchOpenings, PRESETS};
+use shortcuts::{Command, Shortcut, Shortcuts, TextFocus, COMMANDS};
+use annotation::{held_color, toggle_annotation, Annotation, HistoryNote, MOVE_SYMBOLS};
+use theme::{load_themes, pip_offsets, PieceStyle, Theme, PIECE_STYLES};
+use board_image::{board_image, export_game, parse_image_size, save_png, DEFAULT_IMAGE_SIZE, IMAGE_PATH};
+
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
 
 
 // Constants
@@ -27,11 +63,12 @@ pub const GRID_WIDTH: f32 = 75.0;
 pub const GRID_HEIGHT: f32 = 75.0;
 
 pub const PIECE_RADIUS: f32 = 30.0;
+pub const PIECE_COUNT: usize = 4; // Pieces of each type on the board
+pub const PALETTE_I: usize = 38; // Board index of pieces taken from the palette
 
-pub const COLOR_BOARD: Color = Color::new(160.0/255.0, 149.0/255.0, 115.0/255.0 , 1.0); // Hex: #a09573
-pub const COLOR_GRIDSPOT: Color = Color::new(175.0/255.0, 163.0/255.0, 126.0/255.0, 1.0); // Hex: #afa37e
-pub const P1_MOVE: Color = Color::new(0.0, 1.0, 0.0, 1.0);
-pub const P2_MOVE: Color = Color::new(1.0, 0.0, 1.0, 1.0);
+
+pub const CONSOLE_LINES: usize = 200; // Log lines shown in the console
+pub const HIGHLIGHT_RADIUS: f32 = 36.0; // Slightly larger than a piece so the highlight shows around it
 
 pub type Move = Vec<usize>;
 pub type BoardState = [usize; 38];
@@ -70,24 +107,25 @@ impl Piece {
     }
 
     // Draw the piece
-    pub fn draw(&self) {
-        if self.piece_type == 3 {
-            draw_poly(self.pos.0, self.pos.1, 100, PIECE_RADIUS, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 25.0, 0., COLOR_BOARD);
-            draw_poly(self.pos.0, self.pos.1, 100, 20.0, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 15.0, 0., COLOR_BOARD);
-            draw_poly(self.pos.0, self.pos.1, 100, 10.0, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 5.0, 0., COLOR_BOARD);
+    // Every style starts with the outer ring, rings adds one more ring per step
+    pub fn draw(&self, theme: &Theme, style: PieceStyle) {
+        let rings = if style == PieceStyle::Rings { self.piece_type } else { 1 };
+        for ring in 0..rings {
+            draw_poly(self.pos.0, self.pos.1, 100, PIECE_RADIUS - (ring as f32 * 10.0), 0., theme.piece);
+            draw_poly(self.pos.0, self.pos.1, 100, PIECE_RADIUS - (ring as f32 * 10.0) - 5.0, 0., theme.piece_detail);
+
+        }
+
+        if style == PieceStyle::Numerals {
+            let text = self.piece_type.to_string();
+            let text_size = measure_text(&text, None, 36, 1.0);
+            draw_text_ex(&text, self.pos.0 - (text_size.width / 2.0), self.pos.1 + (text_size.height / 2.0), TextParams { font_size: 36, color: theme.piece, ..Default::default() });
 
-        } else if self.piece_type == 2 {
-            draw_poly(self.pos.0, self.pos.1, 100, PIECE_RADIUS, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 25.0, 0., COLOR_BOARD);
-            draw_poly(self.pos.0, self.pos.1, 100, 20.0, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 15.0, 0., COLOR_BOARD);
+        } else if style == PieceStyle::Pips {
+            for offset in pip_offsets(self.piece_type) {
+                draw_circle(self.pos.0 + offset.0, self.pos.1 + offset.1, 5.0, theme.piece);
 
-        } else if self.piece_type == 1 {
-            draw_poly(self.pos.0, self.pos.1, 100, PIECE_RADIUS, 0., BLACK);
-            draw_poly(self.pos.0, self.pos.1, 100, 25.0, 0., COLOR_BOARD);
+            }
 
         }
 
@@ -102,10 +140,19 @@ impl Piece {
 pub enum Action {
     None,
     Dragging(usize),
+    Selected(usize),
     Dropping(usize),
 
 }
 
+// How pieces are moved with the mouse
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InputMode {
+    Drag, // Press on a piece, release on the destination
+    Click, // Click a piece, then click the destination
+
+}
+
 // Drawable Board Struct 
 // The board that is rendered on the screen and all of its logic
 #[derive(Clone)]
@@ -130,6 +177,31 @@ pub struct DrawableBoard {
 
     flipped: bool,
 
+    result: Option<String>,
+
+    editing: bool,
+
+    layout: Layout,
+
+    animations: VecDeque<PieceAnimation>,
+    anim_speed: f32,
+
+    input_mode: InputMode,
+
+    notes: Vec<HistoryNote>, // Comments, symbols and annotations for every history position
+    annotation_start: Option<usize>, // Square where a right drag started
+
+    show_coordinates: bool,
+    show_indices: bool,
+    show_last_move: bool,
+    show_threats: bool,
+
+    threats: Vec<usize>, // Squares the player not to move could reach their goal from
+    threats_key: Option<(BoardState, f64)>, // Position and side the threats were found for
+
+    theme: Theme,
+    piece_style: PieceStyle,
+
 }
 
 impl DrawableBoard {
@@ -156,18 +228,34 @@ impl DrawableBoard {
 
             flipped: false,
 
-        };
+            result: None,
 
-        for i in 0..38 {
-            let pos = d_board.get_pos(i);
-            let piece_type = d_board.boardstate[i];
+            editing: false,
 
-            if piece_type != 0 {
-                d_board.pieces.push(Piece::new(pos, piece_type, i, i));
+            layout: Layout::current(),
 
-            }
+            animations: VecDeque::new(),
+            anim_speed: ANIM_SPEEDS[2],
 
-        }
+            input_mode: InputMode::Drag,
+
+            notes: vec![HistoryNote::new()],
+            annotation_start: None,
+
+            show_coordinates: false,
+            show_indices: false,
+            show_last_move: true,
+            show_threats: false,
+
+            threats: vec![],
+            threats_key: None,
+
+            theme: Theme::light(),
+            piece_style: PieceStyle::Rings,
+
+        };
+
+        d_board.pieces = d_board.create_pieces();
 
         return d_board;
 
@@ -176,13 +264,7 @@ impl DrawableBoard {
     // ========= Helper Functions =========
 
     pub fn boardstate_str(&self) -> String {
-        let mut boardstate_str = String::new();
-        for i in 0..38 {
-            boardstate_str.push_str(&format!("{}", self.boardstate[i]));
-
-        }
-
-        return boardstate_str;
+        return boardstate_to_str(&self.boardstate);
 
     }
 
@@ -268,19 +350,102 @@ impl DrawableBoard {
 
     }
 
-    fn get_pos(&self, i: usize) -> (f32, f32) {
-        if i == 37 {
-            return (self.pos.0 + 450.0, self.pos.1 + 150.0);
+    // The square whose grid cell contains a point, if any
+    fn get_square_at(&self, x: f32, y: f32) -> Option<usize> {
+        for i in 0..38 {
+            let pos = self.get_pos(i);
+            if (pos.0 - x).abs() <= GRID_WIDTH / 2.0 && (pos.1 - y).abs() <= GRID_HEIGHT / 2.0 {
+                return Some(i);
+
+            }
+
+        }
+
+        return None;
+
+    }
+
+    // Mouse position in board space
+    fn mouse_pos(&self) -> (f32, f32) {
+        return self.layout.screen_to_board(mouse_position());
+
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+
+    }
+
+    pub fn set_labels(&mut self, show_coordinates: bool, show_indices: bool) {
+        self.show_coordinates = show_coordinates;
+        self.show_indices = show_indices;
+
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme, piece_style: PieceStyle) {
+        if self.theme != *theme {
+            self.theme = theme.clone();
+
+        }
+        self.piece_style = piece_style;
+
+    }
+
+    pub fn set_highlights(&mut self, show_last_move: bool, show_threats: bool) {
+        self.show_last_move = show_last_move;
+        self.show_threats = show_threats;
+
+    }
+
+    // Finds the squares the opponent of `side` could reach their goal from, only when the position changed
+    // Like check in chess, these are the moves `side` has to answer
+    pub fn update_threats(&mut self, side: f64) {
+        if !self.show_threats || self.threats_key == Some((self.boardstate, side)) {
+            return;
+
+        }
+
+        self.threats = goal_threats(&self.boardstate, -side);
+        self.threats_key = Some((self.boardstate, side));
+
+    }
+
+    // The square drawn where `i` is drawn on the unflipped board
+    // Flipping only changes where squares are drawn, the board itself is never flipped
+    // Applying it twice gives back the same square, so it also works from a drawn square to the board
+    pub fn view_square(&self, i: usize) -> usize {
+        return if self.flipped { flip_square(i) } else { i };
+
+    }
+
+    pub fn set_input_mode(&mut self, input_mode: InputMode) {
+        if input_mode != self.input_mode {
+            self.cancel_move();
+            self.input_mode = input_mode;
 
-        } else if i == 36 {
-            return (self.pos.0 + 450.0, self.pos.1 + 750.0);
- 
         }
 
-        let x = ((i % 6) as f32 * GRID_WIDTH) + self.board_pos.0 + (GRID_WIDTH / 2.0);
-        let y = ((5 - (i / 6)) as f32 * GRID_HEIGHT) + self.board_pos.1 + (GRID_HEIGHT / 2.0);
+    }
+
+    fn get_pos(&self, i: usize) -> (f32, f32) {
+        let pos = square_pos(self.view_square(i));
+        return (self.pos.0 + pos.0, self.pos.1 + pos.1);
+
+    }
+    
+    // A piece for every occupied square, placed where the square is drawn
+    fn create_pieces(&self) -> Vec<Piece> {
+        let mut pieces = vec![];
+        for i in 0..38 {
+            let piece_type = self.boardstate[i];
+            if piece_type != 0 {
+                pieces.push(Piece::new(self.get_pos(i), piece_type, i, i));
+
+            }
+
+        }
 
-        return (x, y);
+        return pieces;
 
     }
     
@@ -303,9 +468,66 @@ impl DrawableBoard {
 
     }
 
+    // Puts a lifted piece down, starting a drop if it lands on another piece
+    fn place_piece(&mut self, id: usize, snap_pos: usize, replace: bool) {
+        if replace {
+            if let Some(piece) = self.get_piece_at(snap_pos) {
+                self.action = Action::Dropping(piece.id);
+
+            }
+            
+        } else {
+            self.action = Action::None;
+
+            self.prev_move = Some(vec![self.pickup_pos.unwrap(), snap_pos]);
+
+        }
+
+        self.exchange_pos = Some(snap_pos);
+
+        self.snap_piece(id, snap_pos);
+
+    }
+
+    // Puts the pieces of an unfinished move back where they were picked up
+    pub fn cancel_move(&mut self) {
+        if self.editing {
+            return;
+
+        }
+
+        match self.action {
+            Action::Dragging(id) | Action::Selected(id) => {
+                if let Some(pickup_pos) = self.pickup_pos {
+                    self.snap_piece(id, pickup_pos);
+
+                }
+
+            },
+            Action::Dropping(id) => {
+                // The moved piece sits on the replaced piece's square until the drop
+                if let (Some(pickup_pos), Some(exchange_pos)) = (self.pickup_pos, self.exchange_pos) {
+                    let moved_id = self.pieces.iter().find(|piece| piece.i == exchange_pos && piece.id != id).map(|piece| piece.id);
+                    if let Some(moved_id) = moved_id {
+                        self.snap_piece(moved_id, pickup_pos);
+
+                    }
+                    self.snap_piece(id, exchange_pos);
+
+                }
+
+            },
+            Action::None => {},
+
+        }
+
+        self.action = Action::None;
+
+    }
+
     // Handles moving a piece
     fn moving(&mut self, id: usize) {
-        let mouse_pos = mouse_position();
+        let mouse_pos = self.mouse_pos();
         if let Some(piece) = self.get_mut_piece(id) {
             piece.pos = mouse_pos;
             
@@ -314,9 +536,23 @@ impl DrawableBoard {
     }
     
     // Main update function
+    // Pieces can't be picked up or placed through a window covering the board, everything else still updates
     // Returns true if the boardstate changed this frame
-    pub fn update(&mut self) -> bool {
-        let mouse_pos = mouse_position();
+    pub fn update(&mut self, mouse_over_ui: bool) -> bool {
+        if self.editing {
+            self.update_editing(mouse_over_ui);
+            return false;
+
+        }
+
+        let mouse_pos = self.mouse_pos();
+
+        // Escape or a right click cancels the move in progress
+        if self.action != Action::None && (is_key_pressed(KeyCode::Escape) || (is_mouse_button_pressed(MouseButton::Right) && !mouse_over_ui)) {
+            self.cancel_move();
+            return false;
+
+        }
 
         match self.action {
             Action::None => {
@@ -326,6 +562,7 @@ impl DrawableBoard {
 
                     if self.history_idx == (self.history.len() - 1) {
                         self.history.push((self.boardstate.clone(), self.prev_move.clone().unwrap_or(vec![])));
+                        self.notes.push(HistoryNote::new());
                         self.history_idx += 1;
 
                     }
@@ -334,9 +571,24 @@ impl DrawableBoard {
                    
                 }
 
+                if !mouse_over_ui {
+                    self.update_annotations();
+
+                }
+
+                if mouse_over_ui || self.animating() {
+                    return state_change;
+
+                }
+
                 for piece in self.pieces.iter() {
                     if piece.is_touching_point(mouse_pos.0, mouse_pos.1) && is_mouse_button_pressed(MouseButton::Left) {
-                        self.action = Action::Dragging(piece.id);
+                        self.action = match self.input_mode {
+                            InputMode::Drag => Action::Dragging(piece.id),
+                            InputMode::Click => Action::Selected(piece.id),
+
+                        };
+                        self.pickup_pos = Some(piece.i);
                         self.boardstate[piece.i] = 0;
                         break;
 
@@ -351,30 +603,33 @@ impl DrawableBoard {
                 self.pickup_pos = Some(self.get_piece(id).unwrap().i);
 
                 if is_mouse_button_released(MouseButton::Left) {
-                    if let (Some(snap_pos), replace) = self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, false) {
-                        if replace {
-                            if let Some(piece) = self.get_piece_at(snap_pos) {
-                                self.action = Action::Dropping(piece.id);
+                    // Releasing outside of the board puts the piece back
+                    if self.get_square_at(mouse_pos.0, mouse_pos.1).is_none() {
+                        self.cancel_move();
 
-                            }
-                            
-                        } else {
-                            self.action = Action::None;
+                    } else if let (Some(snap_pos), replace) = self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, false) {
+                        self.place_piece(id, snap_pos, replace);
 
-                            self.prev_move = Some(vec![self.pickup_pos.unwrap(), snap_pos]);
+                    }
+                    
 
-                        }
+                } else {
+                    self.moving(id);
 
-                        self.exchange_pos = Some(snap_pos);
+                }
 
-                        self.snap_piece(id, snap_pos);
+                return false;
 
+            }
+            Action::Selected(id) => {
+                if is_mouse_button_pressed(MouseButton::Left) && !mouse_over_ui {
+                    // Clicks outside of the squares are ignored, clicking the selected piece again deselects it
+                    match self.get_square_at(mouse_pos.0, mouse_pos.1) {
+                        Some(square) if Some(square) == self.pickup_pos => { self.cancel_move() },
+                        Some(square) => { self.place_piece(id, square, self.boardstate[square] != 0) },
+                        None => {},
 
                     }
-                    
-
-                } else {
-                    self.moving(id);
 
                 }
 
@@ -383,7 +638,27 @@ impl DrawableBoard {
             }
             Action::Dropping(id) => {
                 if is_mouse_button_pressed(MouseButton::Left) {
-                    if let (Some(snap_pos), _) = self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, true) {
+                    let drop_pos = match self.input_mode {
+                        InputMode::Drag => self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, true).0,
+                        InputMode::Click => self.get_square_at(mouse_pos.0, mouse_pos.1).filter(|&i| self.boardstate[i] == 0),
+
+                    };
+
+                    // In click mode a click that misses an open square keeps the piece in hand
+                    if self.input_mode == InputMode::Click && drop_pos.is_none() {
+                        self.moving(id);
+                        return false;
+
+                    }
+
+                    // Otherwise clicking outside of the board cancels the whole move
+                    if self.get_square_at(mouse_pos.0, mouse_pos.1).is_none() {
+                        self.cancel_move();
+                        return false;
+
+                    }
+
+                    if let Some(snap_pos) = drop_pos {
                         self.snap_piece(id, snap_pos);
 
                         self.prev_move = Some(vec![self.pickup_pos.unwrap(), self.exchange_pos.unwrap(), snap_pos]);
@@ -405,506 +680,2192 @@ impl DrawableBoard {
 
     }
 
-    // Main render function
-    pub fn render(&self, engine: &UgiEngine) {
-        // Board
-        draw_poly(self.pos.0 + 450.0, self.pos.1 + 450.0, 4, 450.0, 0.0, COLOR_BOARD);
-        
-        // Radius board corners with r=50
-        draw_rectangle(875.0 + self.pos.0, 0.0 + self.pos.1, 25.0, 900.0, LIGHTGRAY);
-        draw_rectangle(0.0 + self.pos.0, 875.0 + self.pos.1, 900.0, 25.0, LIGHTGRAY);
-        draw_rectangle(0.0 + self.pos.0, 0.0 + self.pos.1, 25.0, 900.0, LIGHTGRAY);
-        draw_rectangle(0.0 + self.pos.0, 0.0 + self.pos.1, 900.0, 25.0, LIGHTGRAY);
-        draw_poly(850.0 + self.pos.0, 450.0 + self.pos.1, 100, 35.355, 0., COLOR_BOARD);
-        draw_poly(450.0 + self.pos.0, 850.0 + self.pos.1, 100, 35.355, 0., COLOR_BOARD);
-        draw_poly(50.0 + self.pos.0, 450.0 + self.pos.1, 100, 35.355, 0., COLOR_BOARD);
-        draw_poly(450.0 + self.pos.0, 50.0 + self.pos.1, 100, 35.355, 0., COLOR_BOARD);
+    // ========= Annotations =========
 
-        // Gridspots
-        for i in 0..36 {
-            let x = self.get_pos(i).0;
-            let y = self.get_pos(i).1;
+    // Right click circles a square and right dragging draws an arrow on the current position
+    fn update_annotations(&mut self) {
+        let mouse_pos = self.mouse_pos();
+
+        // The button may have been released over a window
+        if !is_mouse_button_down(MouseButton::Right) && !is_mouse_button_released(MouseButton::Right) {
+            self.annotation_start = None;
 
-            draw_circle(x, y, 30.0, COLOR_GRIDSPOT);
-            
         }
-        draw_circle(self.get_pos(36).0, self.get_pos(36).1, 30.0, COLOR_GRIDSPOT);
-        draw_circle(self.get_pos(37).0, self.get_pos(37).1, 30.0, COLOR_GRIDSPOT);
 
-        // Pieces
-        for piece in self.pieces.iter() {
-            piece.draw();
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.annotation_start = self.get_square_at(mouse_pos.0, mouse_pos.1);
 
         }
-        
-        // Draw a box around where the piece will be placed
-        if self.action != Action::None {
-            let mouse_pos = mouse_position();
 
-            let dropping = matches!(self.action, Action::Dropping(_));
-            let (snap_pos, _) = self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, dropping);
-            if let Some(snap_pos) = snap_pos {
-                let pos = self.get_pos(snap_pos);
-                draw_rectangle_lines(pos.0 - 37.5, pos.1 - 37.5, 75.0, 75.0, 2.0, BLACK);
+        if is_mouse_button_released(MouseButton::Right) {
+            let start = self.annotation_start.take();
+            let end = self.get_square_at(mouse_pos.0, mouse_pos.1);
+
+            let color = held_color();
+            let annotation = match (start, end) {
+                (Some(square), Some(end)) if square == end => Some(Annotation::Circle { square, color }),
+                (Some(from), Some(to)) => Some(Annotation::Arrow { from, to, color }),
+                _ => None,
+
+            };
+
+            if let Some(annotation) = annotation {
+                toggle_annotation(&mut self.notes[self.history_idx].annotations, annotation);
 
             }
 
         }
 
-        // Player Text
-        let text_params = TextParams { font: None, font_size: 40, font_scale: 1.0, font_scale_aspect: 1.0, rotation: 0.0, color: BLACK };
+    }
 
-        let p1_text = "P1";
-        let p2_text = "P2";
-        let p1_text_size = measure_text(p1_text, None, 40, 1.0);
-        let p2_text_size = measure_text(p2_text, None, 40, 1.0);
-        draw_text_ex(p1_text, self.pos.0 + 125.0 - (p2_text_size.width / 2.0), self.pos.1 + 775.0 + (p2_text_size.height / 2.0), text_params.clone());
-        draw_text_ex(p2_text, self.pos.0 + 125.0 - (p1_text_size.width / 2.0), self.pos.1 + 125.0 + (p1_text_size.height / 2.0), text_params);
-        
-        // Draw a box around the names
-        if engine.side == 1.0 {
-            draw_rectangle_lines(self.pos.0 + 100.0, self.pos.1 + 100.0, 50.0, 50.0, 7.0, BLACK);
-            draw_rectangle_lines(self.pos.0 + 100.0, self.pos.1 + 750.0, 50.0, 50.0, 7.0, P1_MOVE);
+    pub fn note(&self, i: usize) -> &HistoryNote {
+        return &self.notes[i];
 
-        } else {
-            draw_rectangle_lines(self.pos.0 + 100.0, self.pos.1 + 100.0, 50.0, 50.0, 7.0, P1_MOVE);
-            draw_rectangle_lines(self.pos.0 + 100.0, self.pos.1 + 750.0, 50.0, 50.0, 7.0, BLACK);
-            
+    }
 
-        }
+    // The note of the current position
+    pub fn current_note_mut(&mut self) -> &mut HistoryNote {
+        return &mut self.notes[self.history_idx];
 
     }
 
-    // Render a move on the board
-    pub fn render_move(&mut self, mut mv: Move, reverse: bool, color: Color) {
-        if mv == vec![] {
-            return;
+    // Removes the annotations of the current position
+    pub fn clear_annotations(&mut self) {
+        self.notes[self.history_idx].annotations.clear();
 
-        }
+    }
+
+    fn render_annotations(&self) {
+        for annotation in self.notes[self.history_idx].annotations.iter() {
+            match annotation {
+                Annotation::Circle { square, .. } => {
+                    let pos = self.get_pos(*square);
+                    draw_circle_lines(pos.0, pos.1, PIECE_RADIUS + 5.0, 4.0, annotation.color(&self.theme.annotations));
+
+                },
+                Annotation::Arrow { from, to, .. } => {
+                    self.render_arrow(*from, *to, annotation.color(&self.theme.annotations));
+
+                },
+
+            }
 
-        if reverse {
-            mv.reverse();
         }
 
-        for i in 0..mv.len() -1 {
-            self.render_arrow(mv[i], mv[i+1], color);
+        // Arrow that is being drawn
+        if let Some(start) = self.annotation_start {
+            let mouse_pos = self.mouse_pos();
+            if let Some(end) = self.get_square_at(mouse_pos.0, mouse_pos.1).filter(|&end| end != start) {
+                self.render_arrow(start, end, Annotation::Arrow { from: start, to: end, color: held_color() }.color(&self.theme.annotations));
+
+            }
 
         }
 
     }
 
-    // Render an arrow on the board
-    fn render_arrow(&mut self, boardpos_1: usize, boardpos_2: usize, color: Color) {
-        let xy_pos_1 = self.get_pos(boardpos_1);
-        let xy_pos_2 = self.get_pos(boardpos_2);
+    // ========= Annotations =========
 
-        draw_line(xy_pos_1.0, xy_pos_1.1, xy_pos_2.0, xy_pos_2.1, 2.5, color);
-        draw_circle(xy_pos_2.0, xy_pos_2.1, 5.0, color)
+    // ========= Position Editor =========
 
-    }
+    // Enters the position editor
+    pub fn start_editing(&mut self) {
+        self.cancel_move();
+        self.editing = true;
+        self.action = Action::None;
 
-    // Make a move on the board
-    pub fn make_move(&mut self, mv: Move) {
-        let mut new_state = self.boardstate.clone();
-        if mv.len() == 0 {
-            return;
+    }
 
-        }
-        if mv.len() == 2 {
-            let piece = new_state[mv[0]];
-            new_state[mv[0]] = 0;
-            new_state[mv[1]] = piece;
+    // Leaves the position editor, making the edited position the new history root
+    // Fails if the position does not have the right number of pieces
+    pub fn finish_editing(&mut self) -> Result<(), String> {
+        self.validate_position()?;
 
-        } else if mv.len() == 3 {
-            let piece1 = new_state[mv[0]];
-            let piece2 = new_state[mv[1]];
-            new_state[mv[0]] = 0;
-            new_state[mv[1]] = piece1;
-            new_state[mv[2]] = piece2;
-            
-        }
+        self.history = vec![(self.boardstate.clone(), vec![])];
+        self.notes = vec![HistoryNote::new()];
+        self.history_idx = 0;
 
-        let new = DrawableBoard::new(self.pos.0, self.pos.1, new_state);
-        
         self.prev_boardstate = Some(self.boardstate.clone());
-        self.boardstate = new.boardstate;
+        self.prev_move = None;
+        self.result = None;
 
-        self.prev_move = Some(mv.clone());
+        self.editing = false;
 
-        self.pieces = new.pieces;
+        return Ok(());
 
-        self.pos = new.pos;
-        self.board_pos = new.board_pos;
+    }
 
-        self.action = new.action;
+    // Leaves the position editor and restores the position from the history
+    pub fn cancel_editing(&mut self) {
+        self.editing = false;
+        self.load_history(self.history_idx);
 
     }
 
-    // Load a specific move in the history
-    pub fn load_history(&mut self, i: usize) {
-        if i <= self.history.len() - 1 {
-            self.history_idx = i;
+    pub fn clear_board(&mut self) {
+        self.boardstate = [0; 38];
+        self.pieces = vec![];
+        self.action = Action::None;
 
-            let history = self.history[i].clone();
-            let new: DrawableBoard = DrawableBoard::new(self.pos.0, self.pos.1, history.0);
-            
-            self.boardstate = new.boardstate;
-            self.prev_boardstate = Some(new.boardstate);
-            self.prev_move = Some(history.1);
+    }
 
-            self.pieces = new.pieces;
+    fn validate_position(&self) -> Result<(), String> {
+        if self.boardstate[36] != 0 || self.boardstate[37] != 0 {
+            return Err("Goals must be empty".to_string());
 
-            self.pos = new.pos;
-            self.board_pos = new.board_pos;
+        }
 
-            self.action = new.action;
+        for piece_type in 1..=3 {
+            let count = self.boardstate.iter().filter(|&&p| p == piece_type).count();
+            if count != PIECE_COUNT {
+                return Err(format!("Need {} {}-ring pieces, found {}", PIECE_COUNT, piece_type, count));
+
+            }
 
         }
 
-    }
+        return Ok(());
 
-    // Renders a specific move in the history
-    pub fn render_history_mv(&mut self, reverse: bool, i: usize) {
-        if i <= self.history.len() - 1 {
-            let mv = self.history[i].1.clone();
-            self.render_move(mv, reverse, RED);
+    }
 
-        }
+    fn palette_pos(&self, piece_type: usize) -> (f32, f32) {
+        return (self.pos.0 + 125.0 + (piece_type - 1) as f32 * GRID_WIDTH, self.pos.1 + 855.0);
 
     }
 
-    // Reset the board
-    pub fn reset(&mut self) {
-        let new = DrawableBoard::new(self.pos.0, self.pos.1, STARTING_BOARD);
-        
-        self.boardstate = new.boardstate;
-        self.prev_boardstate = Some(new.boardstate);
+    fn next_piece_id(&self) -> usize {
+        return self.pieces.iter().map(|piece| piece.id + 1).max().unwrap_or(0).max(PALETTE_I + 1);
 
-        self.history = vec![(self.boardstate.clone(), vec![])];
-        self.history_idx = 0;
+    }
 
-        self.pieces = new.pieces;
+    // Update function while editing
+    // Pieces are placed without replacements and never create history entries
+    fn update_editing(&mut self, mouse_over_ui: bool) {
+        let mouse_pos = self.mouse_pos();
+
+        match self.action {
+            Action::None => {
+                if mouse_over_ui {
+                    return;
+
+                }
+
+                // Right click removes a piece
+                if is_mouse_button_pressed(MouseButton::Right) {
+                    if let Some(idx) = self.pieces.iter().position(|piece| piece.is_touching_point(mouse_pos.0, mouse_pos.1)) {
+                        let piece = self.pieces.remove(idx);
+                        self.boardstate[piece.i] = 0;
+
+                    }
+
+                    return;
+
+                }
+
+                if !is_mouse_button_pressed(MouseButton::Left) {
+                    return;
+
+                }
+
+                for piece in self.pieces.iter() {
+                    if piece.is_touching_point(mouse_pos.0, mouse_pos.1) {
+                        self.action = Action::Dragging(piece.id);
+                        self.boardstate[piece.i] = 0;
+                        return;
+
+                    }
+
+                }
+
+                // Take a new piece from the palette
+                for piece_type in 1..=3 {
+                    let palette_piece = Piece::new(self.palette_pos(piece_type), piece_type, self.next_piece_id(), PALETTE_I);
+                    if palette_piece.is_touching_point(mouse_pos.0, mouse_pos.1) {
+                        self.action = Action::Dragging(palette_piece.id);
+                        self.pieces.push(palette_piece);
+                        return;
+
+                    }
+
+                }
+
+            },
+            Action::Dragging(id) | Action::Selected(id) | Action::Dropping(id) => {
+                if is_mouse_button_released(MouseButton::Left) {
+                    if let (Some(snap_pos), _) = self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, true) {
+                        self.snap_piece(id, snap_pos);
+
+                    }
+
+                    self.action = Action::None;
+
+                } else {
+                    self.moving(id);
+
+                }
+
+            }
+
+        }
+
+    }
+
+    // ========= Position Editor =========
+
+    // Main render function
+    pub fn render(&self, engine: &UgiEngine) {
+        // Board
+        let theme = &self.theme;
+        draw_poly(self.pos.0 + 450.0, self.pos.1 + 450.0, 4, 450.0, 0.0, theme.board);
+        
+        // Radius board corners with r=50
+        draw_rectangle(875.0 + self.pos.0, 0.0 + self.pos.1, 25.0, 900.0, theme.background);
+        draw_rectangle(0.0 + self.pos.0, 875.0 + self.pos.1, 900.0, 25.0, theme.background);
+        draw_rectangle(0.0 + self.pos.0, 0.0 + self.pos.1, 25.0, 900.0, theme.background);
+        draw_rectangle(0.0 + self.pos.0, 0.0 + self.pos.1, 900.0, 25.0, theme.background);
+        draw_poly(850.0 + self.pos.0, 450.0 + self.pos.1, 100, 35.355, 0., theme.board);
+        draw_poly(450.0 + self.pos.0, 850.0 + self.pos.1, 100, 35.355, 0., theme.board);
+        draw_poly(50.0 + self.pos.0, 450.0 + self.pos.1, 100, 35.355, 0., theme.board);
+        draw_poly(450.0 + self.pos.0, 50.0 + self.pos.1, 100, 35.355, 0., theme.board);
+
+        // Gridspots
+        for i in 0..36 {
+            let x = self.get_pos(i).0;
+            let y = self.get_pos(i).1;
+
+            draw_circle(x, y, 30.0, theme.gridspot);
+            
+        }
+        draw_circle(self.get_pos(36).0, self.get_pos(36).1, 30.0, theme.gridspot);
+        draw_circle(self.get_pos(37).0, self.get_pos(37).1, 30.0, theme.gridspot);
+
+        // Highlights under the pieces
+        if !self.editing {
+            self.render_highlights();
+
+        }
+
+        // Pieces
+        for piece in self.pieces.iter() {
+            piece.draw(theme, self.piece_style);
+
+        }
+
+        // Annotations are hidden while editing the position
+        if !self.editing {
+            self.render_annotations();
+
+        }
+
+        if self.show_indices {
+            self.render_indices();
+
+        }
+
+        // Moving piece on top
+        if let Some(anim) = self.animations.front() {
+            if let Some(piece) = self.pieces.iter().find(|piece| piece.id == anim.id) {
+                piece.draw(theme, self.piece_style);
+
+            }
+
+        }
+        
+        // Draw a box around the selected piece
+        if let (Action::Selected(_), Some(pickup_pos)) = (&self.action, self.pickup_pos) {
+            let pos = self.get_pos(pickup_pos);
+            draw_rectangle_lines(pos.0 - 37.5, pos.1 - 37.5, 75.0, 75.0, 4.0, theme.selection);
+
+        }
+
+        // Draw a box around where the piece will be placed
+        if self.action != Action::None {
+            let mouse_pos = self.mouse_pos();
+
+            let open = matches!(self.action, Action::Dropping(_)) || self.editing;
+            let snap_pos = if self.input_mode == InputMode::Click && !self.editing {
+                self.get_square_at(mouse_pos.0, mouse_pos.1).filter(|&i| !open || self.boardstate[i] == 0)
+
+            } else {
+                self.get_nearest_snap_pos(mouse_pos.0, mouse_pos.1, open).0
+
+            };
+            if let Some(snap_pos) = snap_pos {
+                let pos = self.get_pos(snap_pos);
+                draw_rectangle_lines(pos.0 - 37.5, pos.1 - 37.5, 75.0, 75.0, 2.0, theme.drop_target);
+
+            }
+
+        }
+
+        // Piece palette
+        if self.editing {
+            for piece_type in 1..=3 {
+                Piece::new(self.palette_pos(piece_type), piece_type, 0, PALETTE_I).draw(theme, self.piece_style);
+
+            }
+
+        }
+
+        // Game result
+        if let Some(result) = &self.result {
+            draw_text_ex(result, self.pos.0 + 25.0, self.pos.1 + 60.0, TextParams { font_size: 30, color: RED, ..Default::default() });
+
+        }
+
+        if self.show_coordinates {
+            self.render_coordinates();
+
+        }
+
+        // Player Text
+        let text_params = TextParams { font: None, font_size: 40, font_scale: 1.0, font_scale_aspect: 1.0, rotation: 0.0, color: theme.text };
+
+        let p1_text = "P1";
+        let p2_text = "P2";
+        let p1_text_size = measure_text(p1_text, None, 40, 1.0);
+        let p2_text_size = measure_text(p2_text, None, 40, 1.0);
+        let (p1_pos, p2_pos) = (self.player_pos(1.0), self.player_pos(-1.0));
+        draw_text_ex(p1_text, p1_pos.0 - (p2_text_size.width / 2.0), p1_pos.1 + (p2_text_size.height / 2.0), text_params.clone());
+        draw_text_ex(p2_text, p2_pos.0 - (p1_text_size.width / 2.0), p2_pos.1 + (p1_text_size.height / 2.0), text_params);
+        
+        // Draw a box around the names
+        let (to_move_pos, waiting_pos) = if engine.side == 1.0 { (p1_pos, p2_pos) } else { (p2_pos, p1_pos) };
+        draw_rectangle_lines(waiting_pos.0 - 25.0, waiting_pos.1 - 25.0, 50.0, 50.0, 7.0, theme.text);
+        draw_rectangle_lines(to_move_pos.0 - 25.0, to_move_pos.1 - 25.0, 50.0, 50.0, 7.0, theme.accent);
+
+    }
+
+    // The squares of the move that led to the current position and the squares threatening a goal
+    fn render_highlights(&self) {
+        if self.show_last_move {
+            for square in self.history[self.history_idx].1.iter() {
+                let pos = self.get_pos(*square);
+                draw_circle(pos.0, pos.1, HIGHLIGHT_RADIUS, self.theme.last_move);
+
+            }
+
+        }
+
+        if self.show_threats && !self.game_over() {
+            for square in self.threats.iter() {
+                let pos = self.get_pos(*square);
+                draw_circle(pos.0, pos.1, HIGHLIGHT_RADIUS, self.theme.threat);
+
+            }
+
+        }
+
+    }
+
+    // Files below the bottom row, ranks left of the first column and the goal names next to the goals
+    fn render_coordinates(&self) {
+        let text_params = TextParams { font_size: 24, color: self.theme.label, ..Default::default() };
+
+        let mut labels = vec![];
+        for i in 0..6 {
+            let file_square = self.view_square(i);
+            let file_pos = self.get_pos(file_square);
+            labels.push((square_to_notation(file_square)[..1].to_string(), (file_pos.0, file_pos.1 + GRID_HEIGHT * 0.75)));
+
+            let rank_square = self.view_square(i * 6);
+            let rank_pos = self.get_pos(rank_square);
+            labels.push((square_to_notation(rank_square)[1..].to_string(), (rank_pos.0 - GRID_WIDTH * 0.75, rank_pos.1)));
+
+        }
+        for i in [36, 37] {
+            let goal_pos = self.get_pos(i);
+            labels.push((square_to_notation(i), (goal_pos.0 + GRID_WIDTH * 0.75, goal_pos.1)));
+
+        }
+
+        for (label, pos) in labels.iter() {
+            let size = measure_text(label, None, 24, 1.0);
+            draw_text_ex(label, pos.0 - (size.width / 2.0), pos.1 + (size.height / 2.0), text_params.clone());
+
+        }
+
+    }
+
+    // The index of every square in its top left corner
+    fn render_indices(&self) {
+        let text_params = TextParams { font_size: 20, color: self.theme.index, ..Default::default() };
+        for i in 0..38 {
+            let pos = self.get_pos(i);
+            draw_text_ex(&i.to_string(), pos.0 - GRID_WIDTH / 2.0, pos.1 - GRID_HEIGHT / 2.0 + 14.0, text_params.clone());
+
+        }
+
+    }
+
+    // Render a move on the board
+    // The arrows follow the full path of the move through `before`, the position the move is made in
+    pub fn render_move(&mut self, mv: Move, before: &BoardState, reverse: bool, color: Color) {
+        if mv == vec![] {
+            return;
+
+        }
+
+        let mut polyline = move_polyline(before, &mv);
+        if reverse {
+            polyline.reverse();
+        }
+
+        for i in 0..polyline.len() -1 {
+            self.render_arrow(polyline[i], polyline[i+1], color);
+
+        }
+
+    }
+
+    // Render an arrow on the board
+    fn render_arrow(&self, boardpos_1: usize, boardpos_2: usize, color: Color) {
+        let xy_pos_1 = self.get_pos(boardpos_1);
+        let xy_pos_2 = self.get_pos(boardpos_2);
+
+        draw_line(xy_pos_1.0, xy_pos_1.1, xy_pos_2.0, xy_pos_2.1, 2.5, color);
+        draw_circle(xy_pos_2.0, xy_pos_2.1, 5.0, color)
+
+    }
+
+    // Make a move on the board
+    pub fn make_move(&mut self, mv: Move) {
+        let before = self.boardstate.clone();
+        if mv.len() == 0 || self.editing {
+            return;
+
+        }
+        self.finish_animations();
+
+        let new_state = apply_move(&before, &mv);
+
+        let new = DrawableBoard::new(self.pos.0, self.pos.1, new_state);
+        
+        self.prev_boardstate = Some(self.boardstate.clone());
+        self.boardstate = new.boardstate;
+
+        self.prev_move = Some(mv.clone());
+
+        self.pieces = self.create_pieces();
 
         self.pos = new.pos;
         self.board_pos = new.board_pos;
 
-        self.action = new.action;
+        self.action = new.action;
+
+        self.animate_paths(DrawableBoard::move_paths(&before, &mv, false));
+
+    }
+
+    // Load a specific move in the history
+    pub fn load_history(&mut self, i: usize) {
+        if self.editing {
+            return;
+
+        }
+
+        if i <= self.history.len() - 1 {
+            self.finish_animations();
+
+            let prev_idx = self.history_idx;
+            self.history_idx = i;
+
+            let history = self.history[i].clone();
+            let new: DrawableBoard = DrawableBoard::new(self.pos.0, self.pos.1, history.0);
+            
+            self.boardstate = new.boardstate;
+            self.prev_boardstate = Some(new.boardstate);
+            self.prev_move = Some(history.1.clone());
+
+            self.pieces = self.create_pieces();
+
+            self.pos = new.pos;
+            self.board_pos = new.board_pos;
+
+            self.action = new.action;
+
+            // Stepping one move through the history is animated
+            if i == prev_idx + 1 {
+                let before = self.history[prev_idx].0;
+                self.animate_paths(DrawableBoard::move_paths(&before, &history.1, false));
+
+            } else if i + 1 == prev_idx {
+                let undone_mv = self.history[prev_idx].1.clone();
+                self.animate_paths(DrawableBoard::move_paths(&history.0, &undone_mv, true));
+
+            }
+
+        }
+
+    }
+
+    // ========= Animation =========
+
+    pub fn set_anim_speed(&mut self, anim_speed: f32) {
+        self.anim_speed = anim_speed;
+
+    }
+
+    pub fn animating(&self) -> bool {
+        return !self.animations.is_empty();
+
+    }
+
+    // The paths the pieces of a move travel, in the order they move
+    // Each path ends on the square the piece is on after the move (or before it when reversed)
+    fn move_paths(before: &BoardState, mv: &Move, reverse: bool) -> Vec<Vec<usize>> {
+        if mv.len() < 2 {
+            return vec![];
+
+        }
+
+        let mut paths = vec![move_path(before, mv).unwrap_or(vec![mv[0], mv[1]])];
+        if mv.len() == 3 {
+            paths.push(vec![mv[1], mv[2]]);
+
+        }
+
+        if reverse {
+            paths.reverse();
+            for path in paths.iter_mut() {
+                path.reverse();
+
+            }
+
+        }
+
+        return paths;
+
+    }
+
+    // Queues an animation for every path, placing each piece at the start of its path
+    fn animate_paths(&mut self, paths: Vec<Vec<usize>>) {
+        self.animations.clear();
+        if self.anim_speed <= 0.0 {
+            return;
+
+        }
+
+        for path in paths {
+            let id = match self.get_piece_at(*path.last().unwrap()) {
+                Some(piece) => piece.id,
+                None => continue,
+
+            };
+
+            let anim = PieceAnimation::new(id, path.iter().map(|&i| self.get_pos(i)).collect());
+            let start = anim.start();
+            if let Some(piece) = self.get_mut_piece(id) {
+                piece.pos = start;
+
+            }
+
+            self.animations.push_back(anim);
+
+        }
+
+    }
+
+    // Moves the currently animating piece
+    pub fn update_animation(&mut self, dt: f32) {
+        if self.anim_speed <= 0.0 {
+            self.finish_animations();
+            return;
+
+        }
+
+        let distance = self.anim_speed * dt;
+        if let Some(anim) = self.animations.front_mut() {
+            let id = anim.id;
+            let pos = anim.step(distance);
+            let finished = anim.finished();
+
+            if let Some(piece) = self.get_mut_piece(id) {
+                piece.pos = pos;
+
+            }
+            if finished {
+                self.animations.pop_front();
+
+            }
+
+        }
+
+    }
+
+    // Snaps all animating pieces to their final position
+    fn finish_animations(&mut self) {
+        while let Some(anim) = self.animations.pop_front() {
+            let end = anim.end();
+            if let Some(piece) = self.get_mut_piece(anim.id) {
+                piece.pos = end;
+
+            }
+
+        }
+
+    }
+
+    // ========= Animation =========
+
+    pub fn game_record(&self) -> GameRecord {
+        return GameRecord::from_history(&self.history, &self.notes, self.result.clone());
+
+    }
+
+    // Replaces the history with a game record and jumps to its last position
+    pub fn load_record(&mut self, record: &GameRecord) {
+        self.editing = false;
+        self.history = record.to_history();
+        self.notes = record.position_notes();
+        self.result = record.result.clone();
+
+        let last = self.history.len() - 1;
+        self.history_idx = last;
+        self.load_history(last);
+
+    }
+
+    // Renders a specific move in the history
+    pub fn render_history_mv(&mut self, reverse: bool, i: usize) {
+        if i <= self.history.len() - 1 {
+            let mv = self.history[i].1.clone();
+            let before = self.history[i.max(1) - 1].0;
+            self.render_move(mv, &before, reverse, self.theme.history_move);
+
+        }
+
+    }
+
+    // Reset the board to a new starting position
+    pub fn reset(&mut self, start: BoardState) {
+        let new = DrawableBoard::new(self.pos.0, self.pos.1, start);
+        
+        self.boardstate = new.boardstate;
+        self.prev_boardstate = Some(new.boardstate);
+
+        self.history = vec![(self.boardstate.clone(), vec![])];
+        self.notes = vec![HistoryNote::new()];
+        self.history_idx = 0;
+
+        self.result = None;
+        self.editing = false;
+        self.animations.clear();
+
+        self.pieces = self.create_pieces();
+
+        self.pos = new.pos;
+        self.board_pos = new.board_pos;
+
+        self.action = new.action;
+
+    }
+
+    // Flip the board
+    // Only the view is rotated, the position, history and engine keep their orientation
+    pub fn flip(&mut self) {
+        self.cancel_move();
+        self.finish_animations();
+        self.flipped = !self.flipped;
+
+        for idx in 0..self.pieces.len() {
+            let i = self.pieces[idx].i;
+            if i < 38 {
+                self.pieces[idx].pos = self.get_pos(i);
+
+            }
+
+        }
+
+    }
+
+    // Center of a player's name, in the corner on their side of the board
+    // `side` is 1.0 for P1 and -1.0 for P2
+    pub fn player_pos(&self, side: f64) -> (f32, f32) {
+        let bottom = (side == 1.0) != self.flipped;
+        return (self.pos.0 + 125.0, self.pos.1 + if bottom { 775.0 } else { 125.0 });
+
+    }
+
+    // Checks for game over conditions
+    pub fn game_over(&self) -> bool {
+        return self.boardstate[36] != 0 || self.boardstate[37] != 0;
+
+    }
+
+}
+
+
+// Writes a boardstate as one digit per square
+pub fn boardstate_to_str(boardstate: &BoardState) -> String {
+    let mut boardstate_str = String::new();
+    for piece in boardstate.iter() {
+        boardstate_str.push_str(&format!("{}", piece));
+
+    }
+
+    return boardstate_str;
+
+}
+
+// Center of a square relative to the top left corner of the board
+pub fn square_pos(i: usize) -> (f32, f32) {
+    if i == 37 {
+        return (450.0, 150.0);
+
+    } else if i == 36 {
+        return (450.0, 750.0);
+ 
+    }
+
+    let x = ((i % 6) as f32 * GRID_WIDTH) + 225.0 + (GRID_WIDTH / 2.0);
+    let y = ((5 - (i / 6)) as f32 * GRID_HEIGHT) + 225.0 + (GRID_HEIGHT / 2.0);
+
+    return (x, y);
+
+}
+
+// The same square seen from the other side of the board
+pub fn flip_square(i: usize) -> usize {
+    return match i {
+        36 => 37,
+        37 => 36,
+        _ => 35 - i,
+
+    };
+
+}
+
+// Sets up game `idx` of an engine match and lets the engine play it, returns false once every opening was played
+// `first_to_move` is 0 for P1 and 1 for P2, like the clock
+fn start_match_game(drawable_board: &mut DrawableBoard, engine: &mut UgiEngine, game_clock: &mut GameClock, openings: MatchOpenings, idx: usize, first_to_move: usize) -> bool {
+    let Some((p1_row, p2_row)) = match_opening(openings, idx) else {
+        return false;
+
+    };
+
+    drawable_board.reset(starting_board(&p1_row, &p2_row));
+
+    // With a time control every game is played on the clock
+    game_clock.reset(first_to_move);
+    game_clock.start();
+
+    engine.set_side(if first_to_move == 0 { 1.0 } else { -1.0 });
+    engine.new_search(Mode::Auto, drawable_board);
+
+    return true;
+
+}
+
+// A comment shortened to fit on one line of the history list
+fn comment_line(comment: &str) -> String {
+    let comment = comment.trim();
+    if comment.chars().count() > HISTORY_COMMENT_LENGTH {
+        return format!("({}...)", comment.chars().take(HISTORY_COMMENT_LENGTH).collect::<String>());
+
+    }
+
+    return format!("({})", comment);
+
+}
+
+// Short evaluation for the history list, from P1's perspective
+fn eval_str(p1_score: f64) -> String {
+    if p1_score.abs() >= WIN_SCORE {
+        return if p1_score > 0.0 { "#W".to_string() } else { "#L".to_string() };
+
+    }
+
+    return format!("{:+.0}", p1_score);
+
+}
+
+// Starts the default engine
+// Runs without an engine if none is configured or it fails to start, returning the reason
+fn start_default_engine(engines: &[EngineConfig], default_engine: Option<usize>) -> (UgiEngine, Option<String>) {
+    let config = match default_engine.and_then(|i| engines.get(i)) {
+        Some(config) => config,
+        None => { return (UgiEngine::disconnected(), Some("No engine configured".to_string())) },
+
+    };
+
+    match UgiEngine::new(config) {
+        Ok(engine) => {
+            return (engine, None);
+
+        },
+        Err(err) => {
+            return (UgiEngine::disconnected(), Some(err));
+
+        }
+
+    }
+
+}
+
+fn window_conf() -> Conf {
+    let settings = UserSettings::load();
+
+    Conf {
+        window_title: "Gyges UI".to_owned(),
+        window_height: settings.window_height.max(300),
+        window_width: settings.window_width.max(400),
+        window_resizable: true,
+        ..Default::default() 
+    }
+
+}
+
+fn main() {
+    // Arguments run a command line export without opening a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+
+    }
+
+    // Same as `#[macroquad::main(window_conf)]`, which can't skip the window
+    macroquad::Window::from_config(window_conf(), run_ui());
+
+}
+
+async fn run_ui() {
+    prevent_quit();
+
+    let mut drawable_board = DrawableBoard::new(0.0, 0.0, STARTING_BOARD);
+    let mut eval_bar = EvalBar::new(BOARD_WIDTH + EVAL_BAR_GAP, 0.0, BOARD_HEIGHT);
+
+    rand::srand(miniquad::date::now() as u64);
+
+    let mut user_settings = UserSettings::load();
+    if user_settings.flipped {
+        drawable_board.flip();
+
+    }
+
+    let mut engines: Vec<EngineConfig> = user_settings.engines.clone();
+    let mut default_engine: Option<usize> = user_settings.default_engine;
+
+    // The engine starts analyzing once it has answered the handshake
+    let (mut engine, mut engine_status) = start_default_engine(&engines, default_engine);
+    engine.cache = AnalysisCache::load(CACHE_PATH);
+    let mut engine_test: Option<Receiver<Result<String, String>>> = None;
+
+    let mut maxtime: String = user_settings.max_time.clone();
+    let mut p2_maxtime: String = user_settings.p2_max_time.clone();
+    let mut maxnodes: String = user_settings.max_nodes.clone();
+    let mut matein: String = user_settings.mate_in.clone();
+
+    let mut maxply_option: usize = user_settings.max_ply_option;
+
+    let mut show_search_limits = false;
+    let mut anim_option: usize = user_settings.anim_option;
+    let mut input_option: usize = user_settings.input_option;
+
+    let mut show_display = false;
+    let mut show_coordinates: bool = user_settings.show_coordinates;
+    let mut show_indices: bool = user_settings.show_indices;
+    let mut show_last_move: bool = user_settings.show_last_move;
+    let mut show_threats: bool = user_settings.show_threats;
+
+    let (mut themes, mut themes_error) = load_themes();
+    let mut theme_option: usize = themes.iter().position(|theme| theme.name == user_settings.theme).unwrap_or(0);
+    let mut piece_style_option: usize = user_settings.piece_style.min(PIECE_STYLES.len() - 1);
+    let mut colorblind_arrows: bool = user_settings.colorblind_arrows;
+
+    let mut editor_side_option: usize = 0;
+    let mut editor_error: Option<String> = None;
+
+    let mut show_search_log = false;
+
+    let mut shortcuts: Shortcuts = user_settings.shortcuts.clone();
+    let mut show_shortcuts = false;
+    let mut shortcut_inputs: Vec<String> = COMMANDS.iter().map(|command| shortcuts.get(*command).shortcut_str()).collect();
+    let mut shortcut_status: Option<String> = None;
+    let mut text_focus = TextFocus::new();
+
+    let mut show_command_bar = false;
+    let command_id = hash!();
+    let mut command_text: String = String::new();
+    let mut command_status: Option<String> = None;
+
+    let mut show_new_game = false;
+    let mut new_game_p1: String = arrangement_str(&PRESETS[0].1);
+    let mut new_game_p2: String = arrangement_str(&PRESETS[0].1);
+    let mut preset_option: usize = 0;
+    let mut new_game_error: Option<String> = None;
+    let mut match_openings_option: usize = 0;
+    let mut match_idx: Option<usize> = None;
+    let mut match_score: (usize, usize) = (0, 0);
+
+    let mut show_console = false;
+    let mut console_sent = true;
+    let mut console_recived = true;
+    let mut console_type: String = String::new();
+    let mut console_cmd: String = String::new();
+    let mut console_status: Option<String> = None;
+
+    let mut history_scroll_idx: Option<usize> = None;
+
+    let mut show_engines = engine_status.is_some();
+    let mut new_engine_name: String = String::new();
+    let mut new_engine_path: String = String::new();
+    let mut new_engine_args: String = String::new();
+    let mut new_engine_dir: String = String::new();
+
+    let mut show_game_record = false;
+    let mut record_path: String = RECORD_PATH.to_string();
+    let mut record_status: Option<String> = None;
+    let mut image_path: String = IMAGE_PATH.to_string();
+    let mut image_size: String = DEFAULT_IMAGE_SIZE.to_string();
+
+    let mut game_clock = GameClock::new(TimeControl::None);
+    let mut show_clock_settings = false;
+    let mut time_control_option: usize = 0;
+    let mut first_to_move_option: usize = 0;
+    let mut base_minutes: String = "5".to_string();
+    let mut increment_seconds: String = "3".to_string();
+    let mut period_moves: String = "20".to_string();
+
+    // Main Loop
+    loop {
+        let theme = if colorblind_arrows { themes[theme_option].with_colorblind_arrows() } else { themes[theme_option].clone() };
+        clear_background(theme.background);
+
+        if is_quit_requested() {
+            user_settings.window_width = screen_width() as i32;
+            user_settings.window_height = screen_height() as i32;
+            let _ = user_settings.save();
+
+            let _ = engine.cache.save(CACHE_PATH);
+            engine.quit();
+            break;
+
+        }
+
+        let layout = Layout::current();
+        drawable_board.set_layout(layout);
+
+        // Draw UI
+        let (window_pos, window_size) = layout.controls_window(50.0, 200.0);
+        widgets::Window::new(1, window_pos, window_size)
+            .label("BOARD CONTROLS")
+            .titlebar(true)
+            .movable(false)
+            .ui(&mut ui::root_ui(), |ui| {
+                ui.separator();
+                if ui.button(None, "New Game") {
+                    show_new_game = !show_new_game;
+                    
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Edit Position") && !drawable_board.editing {
+                    if engine.searching || engine.mode != Mode::Disabled {
+                        engine.stop();
+
+                    }
+                    drawable_board.start_editing();
+                    editor_error = None;
+
+                }
+                ui.separator();
+                if ui.button(None, "Flip Board") {
+                    drawable_board.flip();
+
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Time Control") {
+                    show_clock_settings = !show_clock_settings;
+
+                }
+                ui.separator();
+                if ui.button(None, "Game Record") {
+                    show_game_record = !show_game_record;
+
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Engines") {
+                    show_engines = !show_engines;
+
+                }
+                ui.separator();
+                if ui.button(None, "Display") {
+                    show_display = !show_display;
+
+                }
+                ui.separator();
+                ui.combo_box(hash!(), "Move Input", vec!["Drag", "Click"].as_slice(), &mut input_option);
+
+            });
+            
+        let mut new_start: Option<BoardState> = None;
+
+        // Handle Shortcuts
+        // While the command bar or another text input has the focus the keys are typed into it instead
+        let mut save_record = false;
+        let mut load_record = false;
+
+        let shortcut = if show_command_bar || text_focus.typing() { None } else { shortcuts.pressed() };
+        match shortcut {
+            Some(Command::NewGame) => {
+                match parse_starting_board(&new_game_p1, &new_game_p2) {
+                    Ok(start) => { new_start = Some(start) },
+                    Err(err) => {
+                        new_game_error = Some(err);
+                        show_new_game = true;
+
+                    },
+
+                }
+
+            },
+            Some(Command::Flip) => { drawable_board.flip() },
+            Some(Command::ToggleAnalysis) => {
+                if engine.mode == Mode::Analysis {
+                    engine.stop();
+
+                } else if !drawable_board.game_over() && !drawable_board.editing {
+                    engine.new_search(Mode::Analysis, &mut drawable_board);
+
+                }
+
+            },
+            Some(Command::EngineMove) if !drawable_board.game_over() && !drawable_board.editing => {
+                game_clock.set_side_to_move(engine.side);
+                engine.new_search(Mode::Single, &mut drawable_board);
+
+            },
+            Some(Command::ToggleAutoPlay) => {
+                if engine.mode == Mode::Auto {
+                    engine.stop();
+
+                } else if !drawable_board.game_over() && !drawable_board.editing {
+                    game_clock.set_side_to_move(engine.side);
+                    engine.new_search(Mode::Auto, &mut drawable_board);
+
+                }
+
+            },
+            Some(Command::SaveGame) => { save_record = true },
+            Some(Command::LoadGame) => { load_record = true },
+            Some(Command::CopyPosition) => {
+                miniquad::window::clipboard_set(&drawable_board.boardstate_str());
+
+            },
+            Some(Command::ClearAnnotations) => { drawable_board.clear_annotations() },
+            Some(Command::OpenCommandBar) => {
+                show_command_bar = true;
+                command_status = None;
+
+            },
+            Some(Command::Help) => {
+                show_shortcuts = !show_shortcuts;
+                shortcut_inputs = COMMANDS.iter().map(|command| shortcuts.get(*command).shortcut_str()).collect();
+
+            },
+            _ => {},
+
+        }
+
+        if show_command_bar {
+            show_command_bar = widgets::Window::new(15, layout.board_to_screen((250.0, 25.0)), vec2(400.0, 90.0))
+                .label("COMMAND BAR")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    text_focus.input_text(ui, command_id, "Move", &mut command_text);
+                    match &command_status {
+                        Some(status) => { ui.label(None, status) },
+                        None => { ui.label(None, "Enter to play, Escape to close") },
+
+                    }
+
+                });
+
+            // Keys go straight into the move box
+            if shortcut == Some(Command::OpenCommandBar) {
+                ui::root_ui().focus_window(15);
+                ui::root_ui().set_input_focus(command_id);
+                text_focus.focus();
+
+            } else if is_key_pressed(KeyCode::Escape) {
+                show_command_bar = false;
+
+            } else if is_key_pressed(KeyCode::Enter) && drawable_board.action == Action::None && !drawable_board.editing {
+                let notation = command_text.trim().to_string();
+                command_status = match notation_to_move(&notation) {
+                    Some(_) if drawable_board.game_over() => Some("The game is over".to_string()),
+                    Some(mv) if is_legal_move(&drawable_board.boardstate, &mv, engine.side) => {
+                        drawable_board.make_move(mv);
+                        command_text.clear();
+                        None
+
+                    },
+                    Some(_) => Some(format!("Illegal move: {}", notation)),
+                    None => Some(format!("Invalid notation: {}", notation)),
+
+                };
+
+            }
+
+        }
+
+        if show_shortcuts {
+            show_shortcuts = widgets::Window::new(16, layout.board_to_screen((250.0, 125.0)), vec2(400.0, 500.0))
+                .label("SHORTCUTS")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.label(None, "Keys like F5 or Ctrl+S");
+                    ui.separator();
+                    for (i, command) in COMMANDS.iter().enumerate() {
+                        text_focus.input_text(ui, hash!("shortcut", i), command.description(), &mut shortcut_inputs[i]);
+
+                    }
+                    ui.separator();
+                    if ui.button(None, "Apply") {
+                        let mut invalid = vec![];
+                        for (i, command) in COMMANDS.iter().enumerate() {
+                            match Shortcut::parse(&shortcut_inputs[i]) {
+                                Some(shortcut) => { shortcuts.set(*command, shortcut) },
+                                None => { invalid.push(shortcut_inputs[i].trim().to_string()) },
+
+                            }
+
+                        }
+
+                        shortcut_status = if invalid.is_empty() { None } else { Some(format!("Unknown keys: {}", invalid.join(", "))) };
+                        shortcut_inputs = COMMANDS.iter().map(|command| shortcuts.get(*command).shortcut_str()).collect();
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Defaults") {
+                        shortcuts = Shortcuts::new();
+                        shortcut_status = None;
+                        shortcut_inputs = COMMANDS.iter().map(|command| shortcuts.get(*command).shortcut_str()).collect();
+
+                    }
+                    if let Some(status) = &shortcut_status {
+                        ui.label(None, status);
+
+                    }
+                    ui.separator();
+                    ui.label(None, "Left/Right: Undo/Redo, Up: Latest Move");
+                    ui.label(None, "Escape/Right Click: Cancel Move");
+
+                });
+
+        }
+
+        if show_new_game {
+            let mut start_match = false;
+
+            let match_openings = if match_openings_option == 0 { MatchOpenings::Symmetric } else { MatchOpenings::AllPairs };
+
+            show_new_game = widgets::Window::new(14, layout.board_to_screen((250.0, 150.0)), vec2(400.0, 350.0))
+                .label("NEW GAME")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.label(None, "Back rows from each player's left to right");
+                    text_focus.input_text(ui, hash!(), "P1 Back Row", &mut new_game_p1);
+                    text_focus.input_text(ui, hash!(), "P2 Back Row", &mut new_game_p2);
+                    ui.separator();
+                    let preset_names: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+                    ui.combo_box(hash!(), "Preset", preset_names.as_slice(), &mut preset_option);
+                    if ui.button(None, "Use Preset") {
+                        new_game_p1 = arrangement_str(&PRESETS[preset_option].1);
+                        new_game_p2 = new_game_p1.clone();
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Random Symmetric") {
+                        new_game_p1 = arrangement_str(&random_arrangement());
+                        new_game_p2 = new_game_p1.clone();
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Random") {
+                        new_game_p1 = arrangement_str(&random_arrangement());
+                        new_game_p2 = arrangement_str(&random_arrangement());
+
+                    }
+                    ui.separator();
+                    if ui.button(None, "Start Game") {
+                        match parse_starting_board(&new_game_p1, &new_game_p2) {
+                            Ok(start) => {
+                                new_start = Some(start);
+                                new_game_error = None;
+
+                            },
+                            Err(err) => { new_game_error = Some(err) },
+
+                        }
+
+                    }
+                    if let Some(err) = &new_game_error {
+                        ui.label(None, err);
+
+                    }
+
+                    ui.separator();
+                    ui.label(None, "  ---------- ENGINE MATCH ----------");
+                    ui.combo_box(hash!(), "Openings", vec!["Symmetric", "All Pairs"].as_slice(), &mut match_openings_option);
+                    if ui.button(None, "Start Match") {
+                        start_match = true;
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Stop Match") && match_idx.is_some() {
+                        match_idx = None;
+                        engine.stop();
+
+                    }
+                    if let Some(idx) = match_idx {
+                        ui.label(None, &format!("Game {} of {}", idx + 1, match_length(match_openings)));
+
+                    }
+                    ui.label(None, &format!("P1 Wins: {}  P2 Wins: {}", match_score.0, match_score.1));
+
+                });
+
+            if start_match {
+                match_score = (0, 0);
+                match_idx = Some(0);
+                start_match_game(&mut drawable_board, &mut engine, &mut game_clock, match_openings, 0, first_to_move_option);
+
+            }
+
+        }
+
+        if let Some(start) = new_start {
+            match_idx = None;
+            drawable_board.reset(start);
+            game_clock.reset(first_to_move_option);
+
+            if engine.mode != Mode::Disabled {
+                engine.new_search(Mode::Analysis, &mut drawable_board);
+
+            }
+
+        }
+
+        if drawable_board.editing {
+            widgets::Window::new(9, layout.board_to_screen((600.0, 25.0)), vec2(275.0, 175.0))
+                .label("EDIT POSITION")
+                .titlebar(true)
+                .movable(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.label(None, "Drag pieces from the palette,");
+                    ui.label(None, "right click to remove a piece.");
+                    ui.separator();
+                    ui.combo_box(hash!(), "Side to Move", vec!["P1", "P2"].as_slice(), &mut editor_side_option);
+                    ui.separator();
+                    if ui.button(None, "Clear Board") {
+                        drawable_board.clear_board();
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Done") {
+                        match drawable_board.finish_editing() {
+                            Ok(()) => {
+                                editor_error = None;
+                                engine.set_side(if editor_side_option == 0 { 1.0 } else { -1.0 });
+
+                            },
+                            Err(err) => { editor_error = Some(err) },
+
+                        }
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Cancel") {
+                        drawable_board.cancel_editing();
+                        editor_error = None;
+
+                    }
+                    if let Some(err) = &editor_error {
+                        ui.separator();
+                        ui.label(None, err);
+
+                    }
+
+                });
+
+        }
+
+        let (window_pos, window_size) = layout.controls_window(265.0, 125.0);
+        widgets::Window::new(2, window_pos, window_size)
+            .label("ANALYSIS")
+            .titlebar(true)
+            .movable(false)
+            .ui(&mut ui::root_ui(), |ui| {
+                ui.separator();
+                if ui.button(None, "Enable") && !drawable_board.game_over() && !drawable_board.editing {
+                    engine.new_search(Mode::Analysis, &mut drawable_board);
+
+
+                }
+                ui.separator();
+                if ui.button(None, "Disable") && !drawable_board.game_over()  {
+                    engine.stop();
+
+                }
+                ui.separator();
+                if ui.button(None, "Switch Player") {
+                    engine.flip_side();
+
+                    if engine.searching || engine.mode != Mode::Disabled {
+                        engine.new_search(Mode::Analysis, &mut drawable_board);
+
+                    } 
+                    
+                }
+                
+            }); 
+
+        let (window_pos, window_size) = layout.controls_window(405.0, 210.0);
+        widgets::Window::new(3, window_pos, window_size)
+            .label("ANALYSIS INFO")
+            .titlebar(true)
+            .movable(false)
+            .ui(&mut ui::root_ui(), |ui| {
+                if ui.button(None, "Search Log") {
+                    show_search_log = !show_search_log;
+
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Console") {
+                    show_console = !show_console;
+
+                }
+                ui.separator();
+                if let Some(ply) = &engine.best_search.ply {
+                    ui.label(None, format!("Ply: {}", ply).as_str());
+
+                }
+                if let Some(score) = &engine.best_search.score {
+                    ui.label(None, format!("Score: {}", score).as_str());
+
+                }
+                if let Some(best_move) = &engine.best_search.best_move {
+                    ui.label(None, format!("Best Move: {}", move_to_notation(best_move)).as_str());
+
+                }
+                if let Some(nodes) = &engine.best_search.nodes {
+                    ui.label(None, format!("Nodes: {}", nodes).as_str());
+
+                }
+                if let Some(nps) = &engine.best_search.nps {
+                    ui.label(None, format!("NPS: {}", nps).as_str());
+
+                }
+                if let Some(abf) = &engine.best_search.abf {
+                    ui.label(None, format!("ABF: {}", abf).as_str());
+
+                }
+                if let Some(beta_cuts) = &engine.best_search.beta_cuts {
+                    ui.label(None, format!("Beta Cuts: {}", beta_cuts).as_str());
+
+                }
+                if let Some(time) = &engine.best_search.time {
+                    ui.label(None, format!("Time: {}", time).as_str());
+
+                }
+                
+            });
+            
+        if show_console {
+            show_console = widgets::Window::new(13, layout.board_to_screen((50.0, 450.0)), vec2(800.0, 400.0))
+                .label("UGI CONSOLE")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.checkbox(hash!(), "Sent", &mut console_sent);
+                    ui.same_line(0.0);
+                    ui.checkbox(hash!(), "Received", &mut console_recived);
+                    ui.same_line(0.0);
+                    text_focus.input_text(ui, hash!(), "Type", &mut console_type);
+                    ui.separator();
+
+                    text_focus.input_text(ui, hash!(), "Command", &mut console_cmd);
+                    ui.same_line(0.0);
+                    if ui.button(None, "Send") && !console_cmd.trim().is_empty() {
+                        engine.send(console_cmd.trim());
+                        console_cmd.clear();
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Save") {
+                        console_status = match engine.log.save(UGI_LOG_PATH) {
+                            Ok(()) => Some(format!("Saved to {}", UGI_LOG_PATH)),
+                            Err(err) => Some(err.to_string()),
+
+                        };
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Clear") {
+                        engine.log.clear();
+
+                    }
+                    if let Some(status) = &console_status {
+                        ui.label(None, status);
+
+                    }
+                    ui.separator();
+
+                    // Only the most recent lines are drawn
+                    let entries = engine.log.filtered(console_sent, console_recived, &console_type);
+                    for entry in entries.iter().skip(entries.len().saturating_sub(CONSOLE_LINES)) {
+                        ui.label(None, &entry.log_str());
+
+                    }
+
+                });
+
+        }
+
+        if show_search_log {
+            show_search_log = widgets::Window::new(6, layout.board_to_screen((50.0, 550.0)), vec2(800.0, 300.0))
+                .label("SEARCH LOG")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    if ui.button(None, "Export CSV") {
+                        if let Some(search_log) = engine.search_log() {
+                            let _ = search_log.export(SEARCH_LOG_PATH);
+
+                        }
+
+                    }
+                    ui.separator();
+                    ui.label(None, &format!("{:>5} {:>10} {:<12} {:>12} {:>12} {:>8} {:>12} {:>8}", "Depth", "Score", "Best Move", "Nodes", "NPS", "ABF", "Beta Cuts", "Time"));
+                    ui.separator();
+
+                    if let Some(search_log) = engine.search_log() {
+                        for row in search_log.rows.iter() {
+                            let best_move = row.best_move.as_ref().map(move_to_notation).unwrap_or_default();
+                            ui.label(None, &format!("{:>5} {:>10} {:<12} {:>12} {:>12} {:>8.3} {:>12} {:>8.3}", 
+                                row.ply.unwrap_or(0.0), 
+                                row.score.unwrap_or(0.0), 
+                                best_move, 
+                                row.nodes.unwrap_or(0.0), 
+                                row.nps.unwrap_or(0.0), 
+                                row.abf.unwrap_or(0.0), 
+                                row.beta_cuts.unwrap_or(0.0), 
+                                row.time.unwrap_or(0.0)
+                            ));
+
+                        }
+
+                    }
+
+                });
+
+        }
+
+        // Engine tests run in the background and engines that fail while running are reported in the engines window
+        if let Some(Ok(result)) = engine_test.as_ref().map(|test| test.try_recv()) {
+            engine_status = Some(match result {
+                Ok(name) => format!("{} is working", name),
+                Err(err) => err,
+
+            });
+            engine_test = None;
+
+        }
+        if let Some(err) = engine.error.take() {
+            engine_status = Some(err);
+            show_engines = true;
+
+        }
+
+        if show_engines {
+            let mut restart_engine = false;
+
+            show_engines = widgets::Window::new(12, layout.board_to_screen((250.0, 200.0)), vec2(400.0, 400.0))
+                .label("ENGINES")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    let mut remove_idx = None;
+                    for (i, config) in engines.iter().enumerate() {
+                        let marker = if default_engine == Some(i) { "* " } else { "" };
+                        ui.label(None, &format!("{}{} ({})", marker, config.name, config.path));
+                        if ui.button(None, "Set Default") {
+                            default_engine = Some(i);
+
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(None, "Test") {
+                            engine_test = Some(UgiEngine::test_in_background(config));
+                            engine_status = Some(format!("Testing {}...", config.name));
+
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(None, "Remove") {
+                            remove_idx = Some(i);
+
+                        }
+                        ui.separator();
+
+                    }
+
+                    if let Some(i) = remove_idx {
+                        engines.remove(i);
+                        default_engine = match default_engine {
+                            Some(d) if d == i => None,
+                            Some(d) if d > i => Some(d - 1),
+                            d => d,
+
+                        };
+
+                    }
+
+                    ui.label(None, "  ---------- ADD ENGINE ----------");
+                    text_focus.input_text(ui, hash!(), "Name", &mut new_engine_name);
+                    text_focus.input_text(ui, hash!(), "Path", &mut new_engine_path);
+                    text_focus.input_text(ui, hash!(), "Arguments", &mut new_engine_args);
+                    text_focus.input_text(ui, hash!(), "Working Dir", &mut new_engine_dir);
+
+                    let new_engine = EngineConfig {
+                        name: if new_engine_name.trim().is_empty() { new_engine_path.trim().to_string() } else { new_engine_name.trim().to_string() },
+                        path: new_engine_path.trim().to_string(),
+                        args: split_args(&new_engine_args),
+                        working_dir: new_engine_dir.trim().to_string(),
+
+                    };
+                    if ui.button(None, "Test") && !new_engine.path.is_empty() {
+                        engine_test = Some(UgiEngine::test_in_background(&new_engine));
+                        engine_status = Some(format!("Testing {}...", new_engine.name));
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Add") && !new_engine.path.is_empty() {
+                        engines.push(new_engine);
+                        if default_engine.is_none() {
+                            default_engine = Some(engines.len() - 1);
+
+                        }
+
+                        new_engine_name.clear();
+                        new_engine_path.clear();
+                        new_engine_args.clear();
+                        new_engine_dir.clear();
+
+                    }
+
+                    ui.separator();
+                    if ui.button(None, "Restart With Default Engine") {
+                        restart_engine = true;
+
+                    }
+                    if let Some(status) = &engine_status {
+                        ui.separator();
+                        ui.label(None, status);
+
+                    }
+
+                });
+
+            if restart_engine {
+                engine.quit();
+                let cache = std::mem::replace(&mut engine.cache, AnalysisCache::new());
+
+                (engine, engine_status) = start_default_engine(&engines, default_engine);
+                engine.cache = cache;
+
+                if engine_status.is_none() {
+                    engine_status = Some("Engine started".to_string());
+
+                }
+
+            }
+
+        }
+
+        if show_game_record {
+            let mut export_image = false;
+            let mut export_images = false;
+            show_game_record = widgets::Window::new(10, layout.board_to_screen((50.0, 50.0)), vec2(300.0, 205.0))
+                .label("GAME RECORD")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    text_focus.input_text(ui, hash!(), "Path", &mut record_path);
+                    ui.separator();
+                    if ui.button(None, "Save") {
+                        save_record = true;
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Load") {
+                        load_record = true;
+
+                    }
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Image Path", &mut image_path);
+                    text_focus.input_text(ui, hash!(), "Image Size", &mut image_size);
+                    if ui.button(None, "Export Image") {
+                        export_image = true;
+
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Export Game") {
+                        export_images = true;
+
+                    }
+                    if let Some(status) = &record_status {
+                        ui.separator();
+                        ui.label(None, status);
+
+                    }
 
-    }
+                });
 
-    // Flip the board
-    pub fn flip(&mut self) {
-        let mut flipped_boardstate = [0; 38];
+            if export_image || export_images {
+                record_status = match parse_image_size(&image_size) {
+                    Ok(size) => {
+                        let result = if export_image {
+                            // The current position with its annotations and the engine's best move
+                            let boardstate = drawable_board.boardstate;
+                            let arrows: Vec<(Move, BoardState, Color)> = engine.best_search.best_move.iter().map(|mv| (mv.clone(), boardstate, theme.accent)).collect();
+                            let image = board_image(&boardstate, Some(drawable_board.note(drawable_board.history_idx)), &arrows, size, &theme, PIECE_STYLES[piece_style_option]);
+                            save_png(&image, &image_path).map(|_| "Image exported".to_string())
 
-        for i in 0..36 {
-            let piece = self.boardstate[i];
-            if piece == 0 {
-                continue;
+                        } else {
+                            export_game(&drawable_board.history, &drawable_board.notes, &image_path, size, &theme, PIECE_STYLES[piece_style_option]).map(|count| format!("Exported {} positions", count))
+
+                        };
+
+                        Some(result.unwrap_or_else(|err| err))
+
+                    },
+                    Err(err) => Some(err),
+
+                };
 
             }
 
-            let flipped_i = 35 - i;
-            flipped_boardstate[flipped_i] = piece;
+        }
+
+        if save_record {
+            record_status = match drawable_board.game_record().save(&record_path) {
+                Ok(()) => Some("Saved".to_string()),
+                Err(err) => Some(err),
+
+            };
+            show_game_record = true;
 
         }
+        if load_record {
+            record_status = match GameRecord::load(&record_path) {
+                Ok(record) => {
+                    drawable_board.load_record(&record);
+                    if engine.mode != Mode::Disabled {
+                        engine.new_search(Mode::Analysis, &mut drawable_board);
 
-        flipped_boardstate[36] = self.boardstate[37];
-        flipped_boardstate[37] = self.boardstate[36];
+                    }
 
+                    Some("Loaded".to_string())
 
-        let new = DrawableBoard::new(self.pos.0, self.pos.1, flipped_boardstate);
-        
-        self.boardstate = new.boardstate;
-        self.pieces = new.pieces;
-        self.action = new.action;
+                },
+                Err(err) => Some(err),
 
-        self.flipped = !self.flipped;
+            };
+            show_game_record = true;
 
-    }
+        }
 
-    // Checks for game over conditions
-    pub fn game_over(&self) -> bool {
-        return self.boardstate[36] != 0 || self.boardstate[37] != 0;
+        if show_display {
+            let mut reload_themes = false;
+            let theme_names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+            let style_names: Vec<&str> = PIECE_STYLES.iter().map(|style| style.name()).collect();
+            show_display = widgets::Window::new(18, layout.board_to_screen((600.0, 350.0)), vec2(250.0, 250.0))
+                .label("DISPLAY")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.combo_box(hash!(), "Theme", theme_names.as_slice(), &mut theme_option);
+                    ui.combo_box(hash!(), "Pieces", style_names.as_slice(), &mut piece_style_option);
+                    ui.checkbox(hash!(), "Colorblind Arrows", &mut colorblind_arrows);
+                    if ui.button(None, "Reload Themes") {
+                        reload_themes = true;
 
-    }
+                    }
+                    if let Some(err) = &themes_error {
+                        ui.label(None, err);
 
-}
+                    }
+                    ui.separator();
+                    ui.checkbox(hash!(), "Coordinates", &mut show_coordinates);
+                    ui.checkbox(hash!(), "Square Indices", &mut show_indices);
+                    ui.checkbox(hash!(), "Last Move", &mut show_last_move);
+                    ui.checkbox(hash!(), "Threats", &mut show_threats);
 
+                });
 
-fn window_conf() -> Conf {
-    Conf {
-        window_title: "Gyges UI".to_owned(),
-        window_height: 900,
-        window_width: 1300,
-        window_resizable: false,
-        ..Default::default() 
-    }
+            // Custom themes can be edited while the UI is open, the selected theme is kept by name
+            if reload_themes {
+                let name = themes[theme_option].name.clone();
+                (themes, themes_error) = load_themes();
+                theme_option = themes.iter().position(|theme| theme.name == name).unwrap_or(0);
 
-}
+            }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    prevent_quit();
+        }
 
-    let mut drawable_board = DrawableBoard::new(0.0, 0.0, STARTING_BOARD);
+        if show_clock_settings {
+            show_clock_settings = widgets::Window::new(7, layout.board_to_screen((600.0, 50.0)), vec2(300.0, 250.0))
+                .label("TIME CONTROL")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.combo_box(hash!(), "Type", vec!["None", "Sudden Death", "Base + Inc", "Moves/Period"].as_slice(), &mut time_control_option);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Base (min)", &mut base_minutes);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Increment (s)", &mut increment_seconds);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Moves/Period", &mut period_moves);
+                    ui.separator();
+                    ui.combo_box(hash!(), "First Move", vec!["P1", "P2"].as_slice(), &mut first_to_move_option);
+                    ui.separator();
+                    if ui.button(None, "Apply & Reset Clock") {
+                        let base = base_minutes.parse::<f32>().unwrap_or(5.0).max(0.0) * 60.0;
+                        let increment = increment_seconds.parse::<f32>().unwrap_or(0.0).max(0.0);
+                        let moves = period_moves.parse::<usize>().unwrap_or(20).max(1);
 
-    let mut engine = UgiEngine::new("./gyges_engine.exe");
-    engine.send("ugi");
-    engine.new_search(Mode::Analysis, &mut drawable_board);
+                        game_clock.control = match time_control_option {
+                            1 => TimeControl::SuddenDeath { base },
+                            2 => TimeControl::Increment { base, increment },
+                            3 => TimeControl::MovesPerPeriod { base, moves },
+                            _ => TimeControl::None,
 
-    let mut maxtime: String = MAX_TIME.to_string();
+                        };
+                        game_clock.reset(first_to_move_option);
+                        drawable_board.result = None;
 
-    let mut maxply_option: usize = 0;
-    let mut maxply: Option<String> = None;
+                    }
+                    ui.separator();
+                    if ui.button(None, "Start") {
+                        game_clock.start();
 
-    // Main Loop
-    loop {
-        clear_background(LIGHTGRAY);
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Pause") {
+                        game_clock.pause();
 
-        if is_quit_requested() {
-            engine.quit();
-            break;
+                    }
+
+                });
 
         }
 
-        // Draw UI
-        widgets::Window::new(1, vec2(925.0, 50.0), vec2(250.0, 100.0))
-            .label("BOARD CONTROLS")
+        let (window_pos, window_size) = layout.controls_window(630.0, 245.0);
+        widgets::Window::new(4, window_pos, window_size)
+            .label("AUTO PLAY")
             .titlebar(true)
             .movable(false)
             .ui(&mut ui::root_ui(), |ui| {
                 ui.separator();
-                if ui.button(None, "New Game") {
-                    drawable_board.reset();
-
-                    if engine.mode != Mode::Disabled {
-                        engine.new_search(Mode::Analysis, &mut drawable_board);
+                if ui.button(None, "Simulate Game") && !drawable_board.game_over() {
+                    game_clock.set_side_to_move(engine.side);
+                    engine.new_search(Mode::Auto, &mut drawable_board);
 
-                    }
-                    
                 }
                 ui.separator();
-                if ui.button(None, "Flip Board") {
-                    drawable_board.flip();
+                if ui.button(None, "P1 Move") && !drawable_board.game_over() {
+                    engine.set_side(1.0);
+                    game_clock.set_side_to_move(engine.side);
+                    engine.new_search(Mode::Single, &mut drawable_board);
 
                 }
-
-            });
-            
-        widgets::Window::new(2, vec2(925.0, 175.0), vec2(250.0, 125.0))
-            .label("ANALYSIS")
-            .titlebar(true)
-            .movable(false)
-            .ui(&mut ui::root_ui(), |ui| {
                 ui.separator();
-                if ui.button(None, "Enable") && !drawable_board.game_over() {
-                    engine.new_search(Mode::Analysis, &mut drawable_board);
-
+                if ui.button(None, "P2 Move") && !drawable_board.game_over() {
+                    engine.set_side(-1.0);
+                    game_clock.set_side_to_move(engine.side);
+                    engine.new_search(Mode::Single, &mut drawable_board);
 
                 }
                 ui.separator();
-                if ui.button(None, "Disable") && !drawable_board.game_over()  {
+                if ui.button(None, "Stop") && !drawable_board.game_over() && (engine.mode == Mode::Auto || engine.mode == Mode::Single) {
                     engine.stop();
 
                 }
                 ui.separator();
-                if ui.button(None, "Switch Player") {
-                    engine.flip_side();
-
-                    if engine.searching || engine.mode != Mode::Disabled {
-                        engine.new_search(Mode::Analysis, &mut drawable_board);
+                ui.combo_box(hash!(), "Animation", vec!["Off", "Slow", "Normal", "Fast"].as_slice(), &mut anim_option);
+                ui.separator();
+                if ui.button(None, "Search Limits") {
+                    show_search_limits = !show_search_limits;
 
-                    } 
-                    
                 }
-                
-            }); 
 
-        widgets::Window::new(3, vec2(925.0, 325.0), vec2(250.0, 225.0))
-            .label("ANALYSIS INFO")
-            .titlebar(true)
+            });
+
+        if show_search_limits {
+            show_search_limits = widgets::Window::new(8, layout.board_to_screen((600.0, 550.0)), vec2(300.0, 200.0))
+                .label("SEARCH LIMITS")
+                .titlebar(true)
+                .movable(true)
+                .close_button(true)
+                .ui(&mut ui::root_ui(), |ui| {
+                    ui.combo_box(hash!(), "Max Ply", vec!["No Limit", "1", "3", "5", "7"].as_slice(), &mut maxply_option);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "P1 Max Time (s)", &mut maxtime);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "P2 Max Time (s)", &mut p2_maxtime);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Max Nodes", &mut maxnodes);
+                    ui.separator();
+                    text_focus.input_text(ui, hash!(), "Mate In", &mut matein);
+                    ui.separator();
+                    ui.label(None, "Leave nodes and mate empty for no limit");
+
+                });
+
+        }
+
+        // Apply search limits
+        let maxply: Option<f32> = match maxply_option {
+            1 => Some(1.0),
+            2 => Some(3.0),
+            3 => Some(5.0),
+            4 => Some(7.0),
+            _ => None,
+
+        };
+        engine.settings.max_ply = maxply.unwrap_or(MAX_PLY);
+
+        let p1_maxtime_parsed: f32 = maxtime.parse::<f32>().unwrap_or(0.0).min(MAX_TIME);
+        let p2_maxtime_parsed: f32 = p2_maxtime.parse::<f32>().unwrap_or(0.0).min(MAX_TIME);
+        engine.settings.max_time = (p1_maxtime_parsed, p2_maxtime_parsed);
+
+        engine.settings.max_nodes = maxnodes.trim().parse::<u64>().ok();
+        engine.settings.mate_in = matein.trim().parse::<usize>().ok();
+
+        let mut history_jump: Option<usize> = None;
+
+        let (window_pos, window_size) = layout.history_controls_window();
+        widgets::Window::new(11, window_pos, window_size)
             .movable(false)
+            .titlebar(false)
             .ui(&mut ui::root_ui(), |ui| {
-                if let Some(ply) = &engine.best_search.ply {
-                    ui.label(None, format!("Ply: {}", ply).as_str());
+                let last = drawable_board.history.len() - 1;
+                let idx = drawable_board.history_idx;
 
-                }
-                if let Some(score) = &engine.best_search.score {
-                    ui.label(None, format!("Score: {}", score).as_str());
-
-                }
-                if let Some(best_move) = &engine.best_search.best_move {
-                    ui.label(None, format!("Best Move: {:?}", best_move).as_str());
+                if ui.button(None, " |< ") {
+                    history_jump = Some(0);
 
                 }
-                if let Some(nodes) = &engine.best_search.nodes {
-                    ui.label(None, format!("Nodes: {}", nodes).as_str());
+                ui.same_line(0.0);
+                if ui.button(None, " < ") && idx > 0 {
+                    history_jump = Some(idx - 1);
 
                 }
-                if let Some(nps) = &engine.best_search.nps {
-                    ui.label(None, format!("NPS: {}", nps).as_str());
+                ui.same_line(0.0);
+                if ui.button(None, " > ") && idx < last {
+                    history_jump = Some(idx + 1);
 
                 }
-                if let Some(abf) = &engine.best_search.abf {
-                    ui.label(None, format!("ABF: {}", abf).as_str());
+                ui.same_line(0.0);
+                if ui.button(None, " >| ") {
+                    history_jump = Some(last);
 
                 }
-                if let Some(beta_cuts) = &engine.best_search.beta_cuts {
-                    ui.label(None, format!("Beta Cuts: {}", beta_cuts).as_str());
+                ui.same_line(0.0);
+                if ui.button(None, "Clear Marks") {
+                    drawable_board.clear_annotations();
 
                 }
-                if let Some(time) = &engine.best_search.time {
-                    ui.label(None, format!("Time: {}", time).as_str());
 
-                }
-                
             });
-            
-        widgets::Window::new(4, vec2(925.0, 575.0), vec2(250.0, 275.0))
-            .label("AUTO PLAY")
-            .titlebar(true)
+
+        let (window_pos, window_size) = layout.history_window();
+        widgets::Window::new(5, window_pos, window_size)
+            .label("HISTORY")
             .movable(false)
+            .titlebar(true)
             .ui(&mut ui::root_ui(), |ui| {
-                match maxply_option {
-                    0 => { maxply = None },
-                    1 => { maxply = Some("1".to_string()) },
-                    2 => { maxply = Some("3".to_string()) },
-                    3 => { maxply = Some("5".to_string()) },
-                    4 => { maxply = Some("7".to_string()) },
-                    _ => { maxply = None },
+                let idx = drawable_board.history_idx;
+
+                if ui.button(None, if idx == 0 { "> Start" } else { "Start" }) {
+                    history_jump = Some(0);
 
                 }
+                if !drawable_board.note(0).comment.is_empty() {
+                    ui.label(None, &comment_line(&drawable_board.note(0).comment));
 
-                let mut maxtime_parsed: f32 = maxtime.parse::<f32>().unwrap_or(0.0);
-                if maxtime_parsed > MAX_TIME {
-                    maxtime_parsed = MAX_TIME;
+                }
+                if idx == 0 && history_scroll_idx != Some(0) {
+                    ui.scroll_here();
+                    history_scroll_idx = Some(0);
 
                 }
-                engine.settings.max_time = maxtime_parsed;
 
-                if maxply.is_some() {
-                    engine.settings.max_ply = maxply.clone().unwrap().parse::<f32>().unwrap();
+                // Moves are listed in numbered P1/P2 pairs
+                let moves = drawable_board.history.len() - 1;
+                for row in 0..moves.div_ceil(2) {
+                    ui.label(None, &format!("{}.", row + 1));
 
-                } else {
-                    engine.settings.max_ply = MAX_PLY;
+                    for col in 0..2 {
+                        let i = (row * 2) + col + 1;
+                        if i > moves {
+                            break;
 
-                }
+                        }
 
-                ui.separator();
-                if ui.button(None, "Simulate Game") && !drawable_board.game_over() {
-                    engine.new_search(Mode::Auto, &mut drawable_board);
+                        let mut label = move_to_notation(&drawable_board.history[i].1);
+                        label.push_str(drawable_board.note(i).symbol_str());
+                        if let Some(score) = engine.cache.p1_score(&boardstate_to_str(&drawable_board.history[i].0)) {
+                            label.push_str(&format!(" {}", eval_str(score)));
 
-                }
-                ui.separator();
-                if ui.button(None, "P1 Move") && !drawable_board.game_over() {
-                    engine.set_side(1.0);
-                    engine.new_search(Mode::Single, &mut drawable_board);
+                        }
+                        if i == idx {
+                            label = format!("> {}", label);
 
-                }
-                ui.separator();
-                if ui.button(None, "P2 Move") && !drawable_board.game_over() {
-                    engine.set_side(-1.0);
-                    engine.new_search(Mode::Single, &mut drawable_board);
+                        }
 
-                }
-                ui.separator();
-                if ui.button(None, "Stop") && !drawable_board.game_over() && (engine.mode == Mode::Auto || engine.mode == Mode::Single) {
-                    engine.stop();
+                        ui.same_line(HISTORY_MOVE_X + (col as f32 * HISTORY_MOVE_WIDTH));
+                        if ui.button(None, label.as_str()) {
+                            history_jump = Some(i);
+
+                        }
+
+                    }
+
+                    // Comments are shown below their row
+                    for col in 0..2 {
+                        let i = (row * 2) + col + 1;
+                        if i <= moves && !drawable_board.note(i).comment.is_empty() {
+                            ui.label(None, &format!("{} {}", move_to_notation(&drawable_board.history[i].1), comment_line(&drawable_board.note(i).comment)));
+
+                        }
+
+                    }
+
+                    // Keep the current move in view when it changes
+                    if idx.div_ceil(2) == row + 1 && history_scroll_idx != Some(idx) {
+                        ui.scroll_here();
+                        history_scroll_idx = Some(idx);
+
+                    }
 
                 }
-                ui.separator();
-                ui.label(None, "");
-                ui.separator();
-                ui.label(None, "  ---------- SETTINGS ----------");
-                ui.separator();
-                ui.input_text(hash!(), "Max Time (s)", &mut maxtime);
-                ui.separator();
-                ui.combo_box(hash!(), "Max Ply", vec!["No Limit", "1", "3", "5", "7"].as_slice(), &mut maxply_option);
-                ui.separator();
 
             });
 
-        widgets::Window::new(5, vec2(1200.0, 50.0), vec2(75.0, 800.0))
-            .label("HISTORY")
+        let (window_pos, window_size) = layout.history_notes_window();
+        widgets::Window::new(17, window_pos, window_size)
+            .label("NOTES")
             .movable(false)
             .titlebar(true)
             .ui(&mut ui::root_ui(), |ui| {
-                for i in 0..drawable_board.history.len() {
-                    // Highlight current history index
-                    if i == drawable_board.history_idx {
-                        widgets::Group::new(hash!(),vec2(73.0, 22.0))
-                            .draggable(false)
-                            .highlight(true)
-                            .position(vec2(0.0, i as f32 * 22.0))
-                            .ui(ui, |_| {});
+                let has_move = drawable_board.history_idx > 0;
+                let note = drawable_board.current_note_mut();
 
-                    }
+                // The starting position has no move to mark
+                if has_move {
+                    let mut symbol_option = note.symbol.map_or(0, |symbol| symbol + 1);
+                    let mut symbol_names = vec!["None"];
+                    symbol_names.extend(MOVE_SYMBOLS.iter());
 
-                    ui.separator();
-                    if ui.button(vec2(0.0, i as f32 * 22.0), format!("Move {}", i).as_str()) {
-                        drawable_board.load_history(i);
+                    ui.combo_box(hash!(), "Symbol", symbol_names.as_slice(), &mut symbol_option);
+                    note.symbol = if symbol_option == 0 { None } else { Some(symbol_option - 1) };
 
-                        if engine.mode != Mode::Disabled {
-                            engine.new_search(Mode::Analysis, &mut drawable_board);
+                }
+                text_focus.input_text(ui, hash!(), "Comment", &mut note.comment);
 
-                        }
+            });
 
-                    }
+        if let Some(i) = history_jump {
+            drawable_board.load_history(i);
 
-                }
+            if engine.mode != Mode::Disabled {
+                engine.new_search(Mode::Analysis, &mut drawable_board);
 
-            });
+            }
+
+        }
+
+        // Everything on and beside the board is drawn in board space
+        set_camera(&layout.board_camera());
+
+        // Animations are skipped when auto play is too fast to follow them
+        let fast_sim = engine.mode == Mode::Auto && engine.settings.max_time.0.min(engine.settings.max_time.1) < FAST_SIM_TIME;
+        drawable_board.set_anim_speed(if fast_sim { 0.0 } else { ANIM_SPEEDS[anim_option] });
+        drawable_board.update_animation(get_frame_time());
+
+        drawable_board.set_input_mode(if input_option == 1 { InputMode::Click } else { InputMode::Drag });
+        drawable_board.set_labels(show_coordinates, show_indices);
+        drawable_board.set_theme(&theme, PIECE_STYLES[piece_style_option]);
+        drawable_board.set_highlights(show_last_move, show_threats);
+        drawable_board.update_threats(engine.side);
 
         // Update and render board
-        if drawable_board.update() && !drawable_board.game_over() && (engine.mode == Mode::Analysis || engine.mode == Mode::Single) { 
+        let mouse_over_ui = ui::root_ui().is_mouse_over(Vec2::from(mouse_position()));
+        let state_change = drawable_board.update(mouse_over_ui);
+        if state_change {
+            game_clock.press();
+            engine.settings.clock = game_clock.times();
+            engine.continue_auto(&mut drawable_board);
+
+        }
+        if state_change && !drawable_board.game_over() && (engine.mode == Mode::Analysis || engine.mode == Mode::Single) { 
             engine.new_search(Mode::Analysis, &mut drawable_board);
 
         };
         drawable_board.render(&engine);
 
-        // Draw Box around window 
-        draw_rectangle_lines(0.0, 0.0, 1300.0, 900.0, 2.0, BLACK);
+        // Update and render clocks
+        if drawable_board.game_over() {
+            game_clock.pause();
+
+        }
+        if game_clock.update(get_frame_time()) {
+            drawable_board.result = game_clock.result();
+
+            if engine.mode == Mode::Auto || engine.mode == Mode::Single {
+                engine.stop();
+
+            }
+
+        }
+        engine.settings.clock = game_clock.times();
+        // The clocks are on the other side of the board from the names
+        let (p1_pos, p2_pos) = (drawable_board.player_pos(1.0), drawable_board.player_pos(-1.0));
+        game_clock.render(&theme, (p1_pos.0 + 650.0, p1_pos.1), (p2_pos.0 + 650.0, p2_pos.1));
 
         // Update Engine
         engine.update(&mut drawable_board);
 
+        // Engine matches go through the openings one game at a time
+        if let Some(idx) = match_idx {
+            if engine.mode != Mode::Auto {
+                match_idx = None;
+
+                // A game lost on time counts like any other, only stopping auto play ends the match
+                let winner = if drawable_board.boardstate[rules::P1_GOAL] != 0 {
+                    Some(1.0)
+
+                } else if drawable_board.boardstate[rules::P2_GOAL] != 0 {
+                    Some(-1.0)
+
+                } else {
+                    game_clock.winner()
+
+                };
+
+                if let Some(winner) = winner {
+                    if winner == 1.0 { match_score.0 += 1 } else { match_score.1 += 1 }
+
+                    let match_openings = if match_openings_option == 0 { MatchOpenings::Symmetric } else { MatchOpenings::AllPairs };
+                    if start_match_game(&mut drawable_board, &mut engine, &mut game_clock, match_openings, idx + 1, first_to_move_option) {
+                        match_idx = Some(idx + 1);
+
+                    }
+
+                }
+
+            }
+
+        }
+
+        // Update and render eval bar
+        eval_bar.update(&engine);
+        eval_bar.render(&theme, drawable_board.flipped);
+
         // Render best move
         if engine.best_search.best_move.is_some() && !drawable_board.game_over() {
-            drawable_board.render_move(engine.best_search.best_move.clone().unwrap(), false, P1_MOVE);
+            let before = drawable_board.boardstate;
+            drawable_board.render_move(engine.best_search.best_move.clone().unwrap(), &before, false, theme.accent);
 
         }
 
         // Handle History Keybinds
-        if is_key_down(KeyCode::Left) { // Show undo
-            drawable_board.render_history_mv(true, drawable_board.history_idx)
+        // The arrow keys move the cursor while the command bar or another text input has the focus
+        if !show_command_bar && !text_focus.typing() {
+            if is_key_down(KeyCode::Left) { // Show undo
+                drawable_board.render_history_mv(true, drawable_board.history_idx)
 
-        } else if is_key_down(KeyCode::Right) { // Show redo
-            drawable_board.render_history_mv(false, drawable_board.history_idx + 1)
+            } else if is_key_down(KeyCode::Right) { // Show redo
+                drawable_board.render_history_mv(false, drawable_board.history_idx + 1)
 
-        }
-            
-        if is_key_released(KeyCode::Left) { // Undo
-            drawable_board.load_history(drawable_board.history_idx - 1);
+            }
 
-            if engine.mode != Mode::Disabled {
-                engine.new_search(Mode::Analysis, &mut drawable_board);
+            if is_key_released(KeyCode::Left) { // Undo
+                drawable_board.load_history(drawable_board.history_idx - 1);
 
-            }
+                if engine.mode != Mode::Disabled {
+                    engine.new_search(Mode::Analysis, &mut drawable_board);
 
-        } else if is_key_released(KeyCode::Right) { // Redo
-            drawable_board.load_history(drawable_board.history_idx + 1);
+                }
 
-            if engine.mode != Mode::Disabled {
-                engine.new_search(Mode::Analysis, &mut drawable_board);
+            } else if is_key_released(KeyCode::Right) { // Redo
+                drawable_board.load_history(drawable_board.history_idx + 1);
+
+                if engine.mode != Mode::Disabled {
+                    engine.new_search(Mode::Analysis, &mut drawable_board);
+
+                }
+
+            } else if is_key_released(KeyCode::Up) { // Jump to current board
+                drawable_board.load_history(drawable_board.history.len() - 1);
 
             }
-            
-        } else if is_key_released(KeyCode::Up) { // Jump to current board
-            drawable_board.load_history(drawable_board.history.len() - 1);
 
         }
 
 
+        // Save the settings whenever they change
+        let current_settings = UserSettings {
+            max_time: maxtime.clone(),
+            p2_max_time: p2_maxtime.clone(),
+            max_nodes: maxnodes.clone(),
+            mate_in: matein.clone(),
+            max_ply_option: maxply_option,
+            anim_option,
+            input_option,
+            show_coordinates,
+            show_indices,
+            show_last_move,
+            show_threats,
+            theme: themes[theme_option].name.clone(),
+            piece_style: piece_style_option,
+            colorblind_arrows,
+
+            flipped: drawable_board.flipped,
+
+            shortcuts: shortcuts.clone(),
+
+            engines: engines.clone(),
+            default_engine,
+
+            window_width: user_settings.window_width,
+            window_height: user_settings.window_height,
+
+        };
+        if current_settings != user_settings {
+            user_settings = current_settings;
+            let _ = user_settings.save();
+
+        }
+
+        // Draw Box around window 
+        set_default_camera();
+        draw_rectangle_lines(0.0, 0.0, screen_width(), screen_height(), 2.0, BLACK);
+
+        text_focus.end_frame();
         next_frame().await;
 
     }
@@ -0,0 +1,136 @@
+use crate::Move;
+
+// Coordinate notation for Gyges
+// Files a-f run left to right and ranks 1-6 run from P1's side to P2's side.
+// G1 is the goal P1 moves into (index 37) and G2 is the goal P2 moves into (index 36).
+// Moves are written as `start-end`, with `/drop` added for the replaced piece: `b1-c3/e5`.
+
+// Constants
+pub const FILES: [char; 6] = ['a', 'b', 'c', 'd', 'e', 'f'];
+pub const P1_GOAL_STR: &str = "G1";
+pub const P2_GOAL_STR: &str = "G2";
+
+pub fn square_to_notation(i: usize) -> String {
+    return match i {
+        37 => P1_GOAL_STR.to_string(),
+        36 => P2_GOAL_STR.to_string(),
+        _ => format!("{}{}", FILES[i % 6], (i / 6) + 1),
+
+    };
+
+}
+
+pub fn notation_to_square(square: &str) -> Option<usize> {
+    let square = square.trim().to_lowercase();
+    if square == P1_GOAL_STR.to_lowercase() {
+        return Some(37);
+
+    } else if square == P2_GOAL_STR.to_lowercase() {
+        return Some(36);
+
+    }
+
+    let mut chars = square.chars();
+    let file_char = chars.next()?;
+    let file = FILES.iter().position(|&f| f == file_char)?;
+    let rank = chars.as_str().parse::<usize>().ok()?;
+    if !(1..=6).contains(&rank) {
+        return None;
+
+    }
+
+    return Some((rank - 1) * 6 + file);
+
+}
+
+// Writes a move in notation
+// Flipping the board doesn't change the squares, so a move is always written the same way
+pub fn move_to_notation(mv: &Move) -> String {
+    if mv.len() < 2 {
+        return String::new();
+
+    }
+
+    let mut notation = format!("{}-{}", square_to_notation(mv[0]), square_to_notation(mv[1]));
+    if mv.len() == 3 {
+        notation.push_str(&format!("/{}", square_to_notation(mv[2])));
+
+    }
+
+    return notation;
+
+}
+
+// Reads a move written with `move_to_notation`
+pub fn notation_to_move(notation: &str) -> Option<Move> {
+    let (path, drop) = match notation.split_once('/') {
+        Some((path, drop)) => (path, Some(drop)),
+        None => (notation, None),
+
+    };
+    let (start, end) = path.split_once('-')?;
+
+    let mut mv = vec![notation_to_square(start)?, notation_to_square(end)?];
+    if let Some(drop) = drop {
+        mv.push(notation_to_square(drop)?);
+
+    }
+
+    if mv[0] >= 36 {
+        return None;
+
+    }
+
+    return Some(mv);
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_round_trip() {
+        for i in 0..38 {
+            assert_eq!(notation_to_square(&square_to_notation(i)), Some(i));
+
+        }
+
+        assert_eq!(square_to_notation(0), "a1");
+        assert_eq!(square_to_notation(35), "f6");
+        assert_eq!(notation_to_square("g1"), Some(37));
+        assert_eq!(notation_to_square("a7"), None);
+        assert_eq!(notation_to_square("h1"), None);
+
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        let moves: Vec<(Move, &str)> = vec![
+            (vec![1, 13], "b1-b3"),
+            (vec![32, 37], "c6-G1"),
+            (vec![3, 36], "d1-G2"),
+            (vec![1, 14, 28], "b1-c3/e5"),
+            (vec![30, 24, 30], "a6-a5/a6"),
+
+        ];
+
+        for (mv, notation) in moves {
+            assert_eq!(move_to_notation(&mv), notation);
+            assert_eq!(notation_to_move(notation), Some(mv));
+
+        }
+
+    }
+
+    #[test]
+    fn invalid_moves_are_rejected() {
+        assert_eq!(notation_to_move("G1-a1"), None);
+        assert_eq!(notation_to_move("b1b3"), None);
+        assert_eq!(notation_to_move("b1-b7"), None);
+        assert_eq!(notation_to_move("b1-c3/"), None);
+        assert_eq!(move_to_notation(&vec![1]), "");
+
+    }
+
+}
@@ -0,0 +1,200 @@
+use macroquad::rand::gen_range;
+
+use crate::BoardState;
+
+// Constants
+pub const BACK_ROW_PIECES: [usize; 6] = [1, 1, 2, 2, 3, 3];
+
+// Named back-row arrangements, written from the owning player's left to right
+pub const PRESETS: [(&str, Arrangement); 6] = [
+    ("Standard", [3, 2, 1, 1, 2, 3]),
+    ("Inverted", [1, 2, 3, 3, 2, 1]),
+    ("Twos Out", [2, 3, 1, 1, 3, 2]),
+    ("Ones Out", [1, 3, 2, 2, 3, 1]),
+    ("Pairs", [1, 1, 2, 2, 3, 3]),
+    ("Split", [3, 1, 2, 2, 1, 3]),
+
+];
+
+// A back row from its owner's point of view
+pub type Arrangement = [usize; 6];
+
+// Which openings an engine match goes through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchOpenings {
+    Symmetric, // Both players use the same arrangement
+    AllPairs, // Every P1 arrangement against every P2 arrangement
+
+}
+
+// Builds the starting position for both back rows
+// P1's row is read from a to f, P2's row from f to a since P2 sits on the other side
+pub fn starting_board(p1_row: &Arrangement, p2_row: &Arrangement) -> BoardState {
+    let mut boardstate = [0; 38];
+    for i in 0..6 {
+        boardstate[i] = p1_row[i];
+        boardstate[35 - i] = p2_row[i];
+
+    }
+
+    return boardstate;
+
+}
+
+// Every distinct arrangement of the back-row pieces, in lexicographic order
+pub fn all_arrangements() -> Vec<Arrangement> {
+    let mut arrangements = vec![];
+    let mut current = BACK_ROW_PIECES;
+    loop {
+        arrangements.push(current);
+
+        // Next permutation
+        let Some(i) = (0..5).rev().find(|&i| current[i] < current[i + 1]) else {
+            break;
+
+        };
+        let j = (i + 1..6).rev().find(|&j| current[j] > current[i]).unwrap();
+        current.swap(i, j);
+        current[i + 1..].reverse();
+
+    }
+
+    return arrangements;
+
+}
+
+pub fn random_arrangement() -> Arrangement {
+    let mut arrangement = BACK_ROW_PIECES;
+    for i in (1..6).rev() {
+        let j = gen_range(0, i + 1);
+        arrangement.swap(i, j);
+
+    }
+
+    return arrangement;
+
+}
+
+// The arrangements used for game `idx` of an engine match, or `None` once every opening was played
+pub fn match_opening(openings: MatchOpenings, idx: usize) -> Option<(Arrangement, Arrangement)> {
+    let arrangements = all_arrangements();
+    return match openings {
+        MatchOpenings::Symmetric => {
+            arrangements.get(idx).map(|row| (*row, *row))
+
+        },
+        MatchOpenings::AllPairs => {
+            let count = arrangements.len();
+            if idx >= count * count {
+                None
+
+            } else {
+                Some((arrangements[idx / count], arrangements[idx % count]))
+
+            }
+
+        },
+
+    };
+
+}
+
+pub fn match_length(openings: MatchOpenings) -> usize {
+    let count = all_arrangements().len();
+    return match openings {
+        MatchOpenings::Symmetric => count,
+        MatchOpenings::AllPairs => count * count,
+
+    };
+
+}
+
+pub fn arrangement_str(arrangement: &Arrangement) -> String {
+    return arrangement.iter().map(|piece| piece.to_string()).collect();
+
+}
+
+// Reads an arrangement like "321123", which has to use exactly the back-row pieces
+pub fn parse_arrangement(data: &str) -> Result<Arrangement, String> {
+    let digits: Vec<usize> = data.trim().chars().map(|c| c.to_digit(10).map(|d| d as usize)).collect::<Option<Vec<usize>>>().ok_or(format!("Invalid arrangement: {}", data.trim()))?;
+    if digits.len() != 6 {
+        return Err(format!("An arrangement needs 6 pieces: {}", data.trim()));
+
+    }
+
+    let mut sorted = digits.clone();
+    sorted.sort();
+    if sorted != BACK_ROW_PIECES {
+        return Err(format!("An arrangement needs two pieces of each type: {}", data.trim()));
+
+    }
+
+    let mut arrangement = [0; 6];
+    arrangement.copy_from_slice(&digits);
+
+    return Ok(arrangement);
+
+}
+
+// Reads both back rows and builds the starting position
+pub fn parse_starting_board(p1_row: &str, p2_row: &str) -> Result<BoardState, String> {
+    return Ok(starting_board(&parse_arrangement(p1_row)?, &parse_arrangement(p2_row)?));
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_arrangements_are_distinct() {
+        let arrangements = all_arrangements();
+        assert_eq!(arrangements.len(), 90);
+        assert_eq!(arrangements[0], BACK_ROW_PIECES);
+
+        for (i, arrangement) in arrangements.iter().enumerate() {
+            assert!(!arrangements[..i].contains(arrangement));
+
+        }
+
+        for (_, preset) in PRESETS.iter() {
+            assert!(arrangements.contains(preset));
+
+        }
+
+    }
+
+    #[test]
+    fn match_openings_cover_every_game() {
+        assert_eq!(match_length(MatchOpenings::Symmetric), 90);
+        assert_eq!(match_length(MatchOpenings::AllPairs), 8100);
+
+        let (p1_row, p2_row) = match_opening(MatchOpenings::Symmetric, 89).unwrap();
+        assert_eq!(p1_row, p2_row);
+        assert_eq!(match_opening(MatchOpenings::Symmetric, 90), None);
+
+        let arrangements = all_arrangements();
+        assert_eq!(match_opening(MatchOpenings::AllPairs, 91), Some((arrangements[1], arrangements[1])));
+        assert_eq!(match_opening(MatchOpenings::AllPairs, 8100), None);
+
+    }
+
+    #[test]
+    fn starting_board_mirrors_p2() {
+        let boardstate = parse_starting_board("321123", "112233").unwrap();
+        assert_eq!(&boardstate[0..6], &[3, 2, 1, 1, 2, 3]);
+        assert_eq!(&boardstate[30..36], &[3, 3, 2, 2, 1, 1]);
+        assert!(boardstate[6..30].iter().all(|&piece| piece == 0));
+
+    }
+
+    #[test]
+    fn invalid_arrangements_are_rejected() {
+        assert_eq!(parse_arrangement(" 123321 "), Ok([1, 2, 3, 3, 2, 1]));
+        assert!(parse_arrangement("12332").is_err());
+        assert!(parse_arrangement("111222").is_err());
+        assert!(parse_arrangement("12a321").is_err());
+
+    }
+
+}
@@ -0,0 +1,313 @@
+use crate::{BoardState, Move};
+
+// Constants
+pub const P1_GOAL: usize = 37; // Reached from the last row (30..35)
+pub const P2_GOAL: usize = 36; // Reached from the first row (0..5)
+pub const MAX_PATH_SEARCH: usize = 100000; // Node limit when reconstructing a path
+
+// Squares a piece can step to from `i`, including the goal next to the first and last rows
+pub fn neighbors(i: usize) -> Vec<usize> {
+    let mut neighbors = vec![];
+    if i >= 36 {
+        return neighbors;
+
+    }
+
+    let row = i / 6;
+    let col = i % 6;
+
+    if row < 5 { neighbors.push(i + 6) } else { neighbors.push(P1_GOAL) }
+    if row > 0 { neighbors.push(i - 6) } else { neighbors.push(P2_GOAL) }
+    if col > 0 { neighbors.push(i - 1) }
+    if col < 5 { neighbors.push(i + 1) }
+
+    return neighbors;
+
+}
+
+// The row a player has to move from, which is the nonempty row closest to them
+// `side` is 1.0 for P1, who starts on the first row, and -1.0 for P2
+pub fn active_line(boardstate: &BoardState, side: f64) -> Option<usize> {
+    let has_piece = |row: usize| (0..6).any(|col| boardstate[row * 6 + col] != 0);
+    if side == 1.0 {
+        return (0..6).find(|&row| has_piece(row));
+
+    }
+
+    return (0..6).rev().find(|&row| has_piece(row));
+
+}
+
+// The goal a player moves into
+pub fn goal(side: f64) -> usize {
+    return if side == 1.0 { P1_GOAL } else { P2_GOAL };
+
+}
+
+// Squares a player could move from to reach their goal with their next move
+pub fn goal_threats(boardstate: &BoardState, side: f64) -> Vec<usize> {
+    let Some(row) = active_line(boardstate, side) else {
+        return vec![];
+
+    };
+
+    let goal = goal(side);
+    return (row * 6..row * 6 + 6).filter(|&i| boardstate[i] != 0 && move_path(boardstate, &vec![i, goal]).is_some()).collect();
+
+}
+
+// Returns the position after making a move
+pub fn apply_move(boardstate: &BoardState, mv: &Move) -> BoardState {
+    let mut new_state = *boardstate;
+    if mv.len() == 2 {
+        let piece = new_state[mv[0]];
+        new_state[mv[0]] = 0;
+        new_state[mv[1]] = piece;
+
+    } else if mv.len() == 3 {
+        let piece1 = new_state[mv[0]];
+        let piece2 = new_state[mv[1]];
+        new_state[mv[0]] = 0;
+        new_state[mv[1]] = piece1;
+        new_state[mv[2]] = piece2;
+
+    }
+
+    return new_state;
+
+}
+
+// Reconstructs the squares a piece visits during a move, including every bounce square
+// `boardstate` is the position before the move. The path starts at the starting square and
+// ends where the moving piece stops, which is the replaced square for replacement moves.
+// Returns None if the move can't be made in the position.
+pub fn move_path(boardstate: &BoardState, mv: &Move) -> Option<Vec<usize>> {
+    if mv.len() < 2 || mv[0] >= 36 {
+        return None;
+
+    }
+
+    let piece = boardstate[mv[0]];
+    if piece == 0 {
+        return None;
+
+    }
+
+    let mut board = *boardstate;
+    board[mv[0]] = 0;
+
+    let mut search = PathSearch {
+        board,
+        target: mv[1],
+        replace: mv.len() == 3,
+        path: vec![mv[0]],
+        used_edges: vec![],
+        nodes: 0,
+
+    };
+
+    if search.search(mv[0], piece) {
+        return Some(search.path);
+
+    }
+
+    return None;
+
+}
+
+// Whether a move can be made in the position
+// The piece has to reach its target and a replaced piece has to be dropped on an empty square
+pub fn is_valid_move(boardstate: &BoardState, mv: &Move) -> bool {
+    if move_path(boardstate, mv).is_none() {
+        return false;
+
+    }
+
+    if mv.len() == 3 {
+        let drop = mv[2];
+        return drop < 36 && (boardstate[drop] == 0 || drop == mv[0]);
+
+    }
+
+    return true;
+
+}
+
+// Whether a player can make a move in the position
+// On top of `is_valid_move` the piece has to come from the player's active line
+// and can't move into the opponent's goal
+pub fn is_legal_move(boardstate: &BoardState, mv: &Move, side: f64) -> bool {
+    if mv.len() < 2 || Some(mv[0] / 6) != active_line(boardstate, side) || mv[1] == goal(-side) {
+        return false;
+
+    }
+
+    return is_valid_move(boardstate, mv);
+
+}
+
+// The path of the move followed by the drop square of a replaced piece
+// Falls back to the raw move squares if the move can't be reconstructed
+pub fn move_polyline(boardstate: &BoardState, mv: &Move) -> Vec<usize> {
+    let mut polyline = match move_path(boardstate, mv) {
+        Some(path) => path,
+        None => { return mv.clone() },
+
+    };
+
+    if mv.len() == 3 {
+        polyline.push(mv[2]);
+
+    }
+
+    return polyline;
+
+}
+
+// Depth first search for a path through the board
+struct PathSearch {
+    board: BoardState,
+    target: usize,
+    replace: bool,
+
+    path: Vec<usize>,
+    used_edges: Vec<(usize, usize)>,
+    nodes: usize,
+
+}
+
+impl PathSearch {
+    fn search(&mut self, from: usize, remaining: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes > MAX_PATH_SEARCH {
+            return false;
+
+        }
+
+        for to in neighbors(from) {
+            let edge = (from.min(to), from.max(to));
+            if self.used_edges.contains(&edge) {
+                continue;
+
+            }
+
+            let occupied = self.board[to] != 0;
+            let last_step = remaining == 1;
+
+            // Goals and occupied squares can only be reached on the last step
+            if !last_step && (occupied || to >= 36) {
+                continue;
+
+            }
+
+            self.used_edges.push(edge);
+            self.path.push(to);
+
+            let found = if !last_step {
+                self.search(to, remaining - 1)
+
+            } else if to == self.target && occupied == self.replace {
+                true
+
+            } else if occupied {
+                // Bounce off the piece and keep moving
+                self.search(to, self.board[to])
+
+            } else {
+                false
+
+            };
+
+            if found {
+                return true;
+
+            }
+
+            self.used_edges.pop();
+            self.path.pop();
+
+        }
+
+        return false;
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(pieces: &[(usize, usize)]) -> BoardState {
+        let mut boardstate = [0; 38];
+        for &(i, piece) in pieces.iter() {
+            boardstate[i] = piece;
+
+        }
+
+        return boardstate;
+
+    }
+
+    #[test]
+    fn move_path_follows_bounce() {
+        // The one at a1 lands on the one at a2 and bounces one more step to a3
+        let boardstate = board(&[(0, 1), (6, 1)]);
+        assert_eq!(move_path(&boardstate, &vec![0, 12]), Some(vec![0, 6, 12]));
+        assert_eq!(move_polyline(&boardstate, &vec![0, 12]), vec![0, 6, 12]);
+
+    }
+
+    #[test]
+    fn move_path_stops_on_replaced_piece() {
+        let boardstate = board(&[(0, 1), (6, 2)]);
+        assert_eq!(move_path(&boardstate, &vec![0, 6, 20]), Some(vec![0, 6]));
+        assert_eq!(move_polyline(&boardstate, &vec![0, 6, 20]), vec![0, 6, 20]);
+
+        let after = apply_move(&boardstate, &vec![0, 6, 20]);
+        assert_eq!((after[0], after[6], after[20]), (0, 1, 2));
+
+    }
+
+    #[test]
+    fn move_path_rejects_unreachable_targets() {
+        let boardstate = board(&[(0, 1), (6, 1)]);
+        assert_eq!(move_path(&boardstate, &vec![0, 2]), None);
+        assert_eq!(move_path(&boardstate, &vec![3, 4]), None);
+
+        // A replaced piece can't be dropped on another piece
+        let boardstate = board(&[(0, 1), (6, 2), (12, 3)]);
+        assert!(is_valid_move(&boardstate, &vec![0, 6, 18]));
+        assert!(!is_valid_move(&boardstate, &vec![0, 6, 12]));
+
+    }
+
+    #[test]
+    fn goal_is_only_reached_on_the_last_step() {
+        let boardstate = board(&[(32, 1)]);
+        assert_eq!(move_path(&boardstate, &vec![32, P1_GOAL]), Some(vec![32, P1_GOAL]));
+        assert_eq!(active_line(&boardstate, 1.0), Some(5));
+        assert_eq!(goal_threats(&boardstate, 1.0), vec![32]);
+
+        let boardstate = board(&[(26, 2)]);
+        assert_eq!(goal_threats(&boardstate, 1.0), vec![26]);
+        assert_eq!(goal_threats(&board(&[(20, 2)]), 1.0), vec![]);
+
+    }
+
+    #[test]
+    fn legal_moves_start_on_the_active_line() {
+        let boardstate = board(&[(1, 1), (13, 1), (31, 1)]);
+        assert!(is_legal_move(&boardstate, &vec![1, 2], 1.0));
+        assert!(!is_legal_move(&boardstate, &vec![13, 14], 1.0));
+        assert!(is_legal_move(&boardstate, &vec![31, 32], -1.0));
+        assert!(!is_legal_move(&boardstate, &vec![1, 2], -1.0));
+
+        // A player can't move into the opponent's goal
+        assert!(is_valid_move(&boardstate, &vec![1, P2_GOAL]));
+        assert!(!is_legal_move(&boardstate, &vec![1, P2_GOAL], 1.0));
+        assert!(is_legal_move(&board(&[(1, 1)]), &vec![1, P2_GOAL], -1.0));
+
+    }
+
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+
+use crate::ugi_engine::SearchInfo;
+
+// Constants
+pub const SEARCH_LOG_PATH: &str = "./search_log.csv";
+pub const CSV_HEADER: &str = "depth,score,best_move,nodes,nps,abf,beta_cuts,time";
+
+// Keeps one row per completed ply of a position's search
+// Searching the same position again overwrites the rows of the depths it reaches
+#[derive(Debug, Clone)]
+pub struct SearchLog {
+    pub rows: Vec<SearchInfo>,
+
+}
+
+impl SearchLog {
+    pub fn new() -> SearchLog {
+        return SearchLog {
+            rows: vec![],
+
+        };
+
+    }
+
+    pub fn record(&mut self, search_info: &SearchInfo) {
+        let ply = match search_info.ply {
+            Some(ply) => ply,
+            None => return,
+
+        };
+
+        for row in self.rows.iter_mut() {
+            if row.ply == Some(ply) {
+                *row = search_info.clone();
+                return;
+
+            }
+
+        }
+
+        self.rows.push(search_info.clone());
+        self.rows.sort_by(|a, b| a.ply.partial_cmp(&b.ply).unwrap());
+
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for row in self.rows.iter() {
+            let best_move = match &row.best_move {
+                Some(mv) => mv.iter().map(|i| i.to_string()).collect::<Vec<String>>().join("|"),
+                None => String::new(),
+
+            };
+
+            let fields = [
+                opt_str(row.ply),
+                opt_str(row.score),
+                best_move,
+                opt_str(row.nodes),
+                opt_str(row.nps),
+                opt_str(row.abf),
+                opt_str(row.beta_cuts),
+                opt_str(row.time),
+
+            ];
+            csv.push_str(&format!("{}\n", fields.join(",")));
+
+        }
+
+        return csv;
+
+    }
+
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        return fs::write(path, self.to_csv());
+
+    }
+
+}
+
+fn opt_str(value: Option<f64>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => String::new(),
+
+    }
+
+}
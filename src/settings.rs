@@ -0,0 +1,385 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::animation::ANIM_SPEEDS;
+use crate::shortcuts::Shortcuts;
+use crate::theme::DEFAULT_THEME;
+use crate::ugi_engine::{EngineConfig, MAX_TIME};
+
+// Constants
+pub const SETTINGS_VERSION: u32 = 2; // Version 1 stored a single `engine_path`
+pub const SETTINGS_FILE: &str = "settings.txt";
+pub const APP_DIR: &str = "GygesUI";
+
+pub const DEFAULT_ENGINE_PATH: &str = "./gyges_engine.exe";
+pub const DEFAULT_WINDOW_WIDTH: i32 = 1475;
+pub const DEFAULT_WINDOW_HEIGHT: i32 = 900;
+pub const MAX_PLY_OPTIONS: usize = 5; // No Limit, 1, 3, 5 and 7 in the Search Limits window
+pub const INPUT_OPTIONS: usize = 2; // Drag and Click
+
+// User settings that are kept between sessions
+// Stored as `key=value` lines, starting with the schema version
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSettings {
+    pub max_time: String,
+    pub p2_max_time: String,
+    pub max_nodes: String,
+    pub mate_in: String,
+    pub max_ply_option: usize,
+    pub anim_option: usize,
+    pub input_option: usize, // 0 drags pieces, 1 moves them with two clicks
+    pub show_coordinates: bool,
+    pub show_indices: bool,
+    pub show_last_move: bool,
+    pub show_threats: bool,
+    pub theme: String, // Name of the theme
+    pub piece_style: usize, // Index into `PIECE_STYLES`
+    pub colorblind_arrows: bool,
+
+    pub flipped: bool,
+
+    pub shortcuts: Shortcuts,
+
+    pub engines: Vec<EngineConfig>,
+    pub default_engine: Option<usize>,
+
+    pub window_width: i32,
+    pub window_height: i32,
+
+}
+
+impl UserSettings {
+    pub fn new() -> UserSettings {
+        return UserSettings {
+            max_time: MAX_TIME.to_string(),
+            p2_max_time: MAX_TIME.to_string(),
+            max_nodes: String::new(),
+            mate_in: String::new(),
+            max_ply_option: 0,
+            anim_option: 2,
+            input_option: 0,
+            show_coordinates: false,
+            show_indices: false,
+            show_last_move: true,
+            show_threats: false,
+            theme: DEFAULT_THEME.to_string(),
+            piece_style: 0,
+            colorblind_arrows: false,
+
+            flipped: false,
+
+            shortcuts: Shortcuts::new(),
+
+            engines: vec![EngineConfig::new("Gyges", DEFAULT_ENGINE_PATH)],
+            default_engine: Some(0),
+
+            window_width: DEFAULT_WINDOW_WIDTH,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+
+        };
+
+    }
+
+    // Platform config directory, falling back to the working directory
+    pub fn path() -> PathBuf {
+        let config_dir = if cfg!(windows) {
+            env::var_os("APPDATA").map(PathBuf::from)
+
+        } else if cfg!(target_os = "macos") {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+
+        } else {
+            env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or(env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+
+        };
+
+        return match config_dir {
+            Some(dir) => dir.join(APP_DIR).join(SETTINGS_FILE),
+            None => PathBuf::from(SETTINGS_FILE),
+
+        };
+
+    }
+
+    // Loads the settings file, using the defaults if it is missing
+    pub fn load() -> UserSettings {
+        return match fs::read_to_string(UserSettings::path()) {
+            Ok(data) => UserSettings::parse(&data),
+            Err(_) => UserSettings::new(),
+
+        };
+
+    }
+
+    // Reads the contents of a settings file, using the defaults if it is from an unknown version
+    // Values that fail to parse keep their default
+    pub fn parse(data: &str) -> UserSettings {
+        let mut settings = UserSettings::new();
+
+        let mut version = None;
+        let mut engines = vec![];
+        for line in data.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+
+            };
+
+            match key {
+                "version" => { version = value.parse::<u32>().ok() },
+                "max_time" => { settings.max_time = value.to_string() },
+                "p2_max_time" => { settings.p2_max_time = value.to_string() },
+                "max_nodes" => { settings.max_nodes = value.to_string() },
+                "mate_in" => { settings.mate_in = value.to_string() },
+                "max_ply_option" => { settings.max_ply_option = parse_option(value, MAX_PLY_OPTIONS, settings.max_ply_option) },
+                "anim_option" => { settings.anim_option = parse_option(value, ANIM_SPEEDS.len(), settings.anim_option) },
+                "input_option" => { settings.input_option = parse_option(value, INPUT_OPTIONS, settings.input_option) },
+                "show_coordinates" => { settings.show_coordinates = value.parse().unwrap_or(settings.show_coordinates) },
+                "show_indices" => { settings.show_indices = value.parse().unwrap_or(settings.show_indices) },
+                "show_last_move" => { settings.show_last_move = value.parse().unwrap_or(settings.show_last_move) },
+                "show_threats" => { settings.show_threats = value.parse().unwrap_or(settings.show_threats) },
+                "theme" => { settings.theme = value.to_string() },
+                "piece_style" => { settings.piece_style = value.parse().unwrap_or(settings.piece_style) },
+                "colorblind_arrows" => { settings.colorblind_arrows = value.parse().unwrap_or(settings.colorblind_arrows) },
+                "flipped" => { settings.flipped = value.parse().unwrap_or(settings.flipped) },
+                "shortcut" => { settings.shortcuts.set_from_str(value) },
+                "engine_path" => { engines.push(EngineConfig::new("Gyges", value)) },
+                "engine" => { engines.extend(parse_engine(value)) },
+                "default_engine" => { settings.default_engine = if value == "none" { None } else { value.parse().ok() } },
+                "window_width" => { settings.window_width = value.parse().unwrap_or(settings.window_width) },
+                "window_height" => { settings.window_height = value.parse().unwrap_or(settings.window_height) },
+                _ => {}
+
+            }
+
+        }
+
+        match version {
+            Some(1) => { settings.default_engine = Some(0) },
+            Some(SETTINGS_VERSION) => {},
+            _ => { return UserSettings::new() },
+
+        }
+
+        settings.engines = engines;
+        if settings.default_engine.is_some_and(|i| i >= settings.engines.len()) {
+            settings.default_engine = None;
+
+        }
+
+        return settings;
+
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = UserSettings::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+
+        }
+
+        return fs::write(path, self.settings_str()).map_err(|err| err.to_string());
+
+    }
+
+    // The contents of the settings file
+    pub fn settings_str(&self) -> String {
+        let mut data = vec![
+            format!("version={}", SETTINGS_VERSION),
+            format!("max_time={}", self.max_time),
+            format!("p2_max_time={}", self.p2_max_time),
+            format!("max_nodes={}", self.max_nodes),
+            format!("mate_in={}", self.mate_in),
+            format!("max_ply_option={}", self.max_ply_option),
+            format!("anim_option={}", self.anim_option),
+            format!("input_option={}", self.input_option),
+            format!("show_coordinates={}", self.show_coordinates),
+            format!("show_indices={}", self.show_indices),
+            format!("show_last_move={}", self.show_last_move),
+            format!("show_threats={}", self.show_threats),
+            format!("theme={}", self.theme),
+            format!("piece_style={}", self.piece_style),
+            format!("colorblind_arrows={}", self.colorblind_arrows),
+            format!("flipped={}", self.flipped),
+            format!("window_width={}", self.window_width),
+            format!("window_height={}", self.window_height),
+
+        ];
+
+        for shortcut in self.shortcuts.settings_strs() {
+            data.push(format!("shortcut={}", shortcut));
+
+        }
+        for engine in self.engines.iter() {
+            let args: Vec<String> = engine.args.iter().map(|arg| escape_field(arg, &['|', ' '])).collect();
+            data.push(format!("engine={}|{}|{}|{}", escape_field(&engine.name, &['|']), escape_field(&engine.path, &['|']), args.join(" "), escape_field(&engine.working_dir, &['|'])));
+
+        }
+        // Written even without a default engine, a missing key means the first engine
+        match self.default_engine {
+            Some(default_engine) => { data.push(format!("default_engine={}", default_engine)) },
+            None => { data.push("default_engine=none".to_string()) },
+
+        }
+
+        return data.join("\n") + "\n";
+
+    }
+
+}
+
+// Reads the index of a combo box option, keeping the default if there is no such option
+fn parse_option(value: &str, options: usize, default: usize) -> usize {
+    return value.parse().ok().filter(|option| *option < options).unwrap_or(default);
+
+}
+
+// Reads an engine written as `name|path|args|working_dir`
+// The arguments are separated by spaces, see `escape_field` for how separators inside the fields are written
+fn parse_engine(value: &str) -> Option<EngineConfig> {
+    let parts: Vec<&str> = value.split('|').collect();
+    if parts.len() != 4 || parts[1].is_empty() {
+        return None;
+
+    }
+
+    return Some(EngineConfig {
+        name: unescape_field(parts[0]),
+        path: unescape_field(parts[1]),
+        args: parts[2].split_whitespace().map(unescape_field).collect(),
+        working_dir: unescape_field(parts[3]),
+
+    });
+
+}
+
+// Writes `%` and the separators of a field as `%` and their hex code, so `my book.txt` becomes `my%20book.txt`
+// Backslashes are left alone to keep Windows paths readable
+fn escape_field(value: &str, separators: &[char]) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if c == '%' || separators.contains(&c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+
+        } else {
+            escaped.push(c);
+
+        }
+
+    }
+
+    return escaped;
+
+}
+
+// Reverses `escape_field`, a `%` that isn't followed by a hex code is kept as it is
+fn unescape_field(value: &str) -> String {
+    let mut unescaped = String::new();
+
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        unescaped.push_str(&rest[..start]);
+
+        match rest.get(start + 1..start + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(code) => {
+                unescaped.push(code as char);
+                rest = &rest[start + 3..];
+
+            },
+            None => {
+                unescaped.push('%');
+                rest = &rest[start + 1..];
+
+            },
+
+        }
+
+    }
+    unescaped.push_str(rest);
+
+    return unescaped;
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = UserSettings::new();
+        settings.max_time = "5".to_string();
+        settings.show_threats = true;
+        settings.theme = "High Contrast".to_string();
+        settings.flipped = true;
+        settings.engines.push(EngineConfig::new("Test", "./test_engine"));
+        settings.default_engine = Some(1);
+        settings.window_width = 1920;
+
+        assert_eq!(UserSettings::parse(&settings.settings_str()), settings);
+
+    }
+
+    #[test]
+    fn engines_round_trip() {
+        let mut settings = UserSettings::new();
+        settings.engines = vec![EngineConfig {
+            name: "Gyges | dev 100%".to_string(),
+            path: "C:\\Program Files\\Gyges|dev\\engine.exe".to_string(),
+            args: vec!["--book".to_string(), "my book.txt".to_string(), "--threads=4".to_string()],
+            working_dir: "C:\\Program Files\\Gyges".to_string(),
+
+        }];
+        settings.default_engine = None;
+
+        let data = settings.settings_str();
+        assert!(data.contains("engine=Gyges %7C dev 100%25|C:\\Program Files\\Gyges%7Cdev\\engine.exe|--book my%20book.txt --threads=4|C:\\Program Files\\Gyges\n"));
+        assert!(data.contains("default_engine=none\n"));
+        assert_eq!(UserSettings::parse(&data), settings);
+
+    }
+
+    #[test]
+    fn version_1_is_migrated() {
+        let settings = UserSettings::parse("version=1\nmax_time=5\nflipped=true\nengine_path=./old_engine.exe\n");
+
+        assert_eq!(settings.max_time, "5");
+        assert!(settings.flipped);
+        assert_eq!(settings.engines, vec![EngineConfig::new("Gyges", "./old_engine.exe")]);
+        assert_eq!(settings.default_engine, Some(0));
+
+    }
+
+    #[test]
+    fn unknown_versions_use_the_defaults() {
+        assert_eq!(UserSettings::parse("max_time=5\n"), UserSettings::new());
+        assert_eq!(UserSettings::parse("version=99\nmax_time=5\n"), UserSettings::new());
+
+    }
+
+    #[test]
+    fn invalid_values_keep_their_default() {
+        let settings = UserSettings::parse(&format!("version={}\nanim_option=fast\nwindow_width=\nshow_indices=true\n", SETTINGS_VERSION));
+        assert_eq!(settings.anim_option, UserSettings::new().anim_option);
+        assert_eq!(settings.window_width, DEFAULT_WINDOW_WIDTH);
+        assert!(settings.show_indices);
+
+    }
+
+    #[test]
+    fn out_of_range_options_keep_their_default() {
+        let settings = UserSettings::parse(&format!("version={}\nanim_option=7\nmax_ply_option=9\n", SETTINGS_VERSION));
+        assert_eq!(settings.anim_option, UserSettings::new().anim_option);
+        assert_eq!(settings.max_ply_option, UserSettings::new().max_ply_option);
+
+        let settings = UserSettings::parse(&format!("version={}\nanim_option=3\nmax_ply_option=4\n", SETTINGS_VERSION));
+        assert_eq!((settings.anim_option, settings.max_ply_option), (3, 4));
+
+        let settings = UserSettings::parse(&format!("version={}\ninput_option=2\n", SETTINGS_VERSION));
+        assert_eq!(settings.input_option, UserSettings::new().input_option);
+        assert_eq!(UserSettings::parse(&format!("version={}\ninput_option=1\n", SETTINGS_VERSION)).input_option, 1);
+
+    }
+
+}
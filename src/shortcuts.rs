@@ -0,0 +1,288 @@
+use macroquad::prelude::*;
+use macroquad::ui::{Id, Ui};
+
+// Keys that can be bound, by the name used in the settings file
+// The arrow keys and Escape are always used for the history and cancelling moves
+pub const KEY_NAMES: [(&str, KeyCode); 51] = [
+    ("A", KeyCode::A), ("B", KeyCode::B), ("C", KeyCode::C), ("D", KeyCode::D), ("E", KeyCode::E),
+    ("F", KeyCode::F), ("G", KeyCode::G), ("H", KeyCode::H), ("I", KeyCode::I), ("J", KeyCode::J),
+    ("K", KeyCode::K), ("L", KeyCode::L), ("M", KeyCode::M), ("N", KeyCode::N), ("O", KeyCode::O),
+    ("P", KeyCode::P), ("Q", KeyCode::Q), ("R", KeyCode::R), ("S", KeyCode::S), ("T", KeyCode::T),
+    ("U", KeyCode::U), ("V", KeyCode::V), ("W", KeyCode::W), ("X", KeyCode::X), ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0), ("1", KeyCode::Key1), ("2", KeyCode::Key2), ("3", KeyCode::Key3), ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5), ("6", KeyCode::Key6), ("7", KeyCode::Key7), ("8", KeyCode::Key8), ("9", KeyCode::Key9),
+    ("F1", KeyCode::F1), ("F2", KeyCode::F2), ("F3", KeyCode::F3), ("F4", KeyCode::F4), ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6), ("F7", KeyCode::F7), ("F8", KeyCode::F8), ("F9", KeyCode::F9), ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11), ("F12", KeyCode::F12),
+    ("Enter", KeyCode::Enter), ("Space", KeyCode::Space), ("Tab", KeyCode::Tab),
+
+];
+pub const INPUT_HEIGHT: f32 = 19.0; // Height of a single line text input
+
+// Everything that can be done with a shortcut
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    NewGame,
+    Flip,
+    ToggleAnalysis,
+    EngineMove,
+    ToggleAutoPlay,
+    SaveGame,
+    LoadGame,
+    CopyPosition,
+    ClearAnnotations,
+    OpenCommandBar,
+    Help,
+
+}
+
+pub const COMMANDS: [Command; 11] = [
+    Command::NewGame,
+    Command::Flip,
+    Command::ToggleAnalysis,
+    Command::EngineMove,
+    Command::ToggleAutoPlay,
+    Command::SaveGame,
+    Command::LoadGame,
+    Command::CopyPosition,
+    Command::ClearAnnotations,
+    Command::OpenCommandBar,
+    Command::Help,
+
+];
+
+impl Command {
+    // Key in the settings file
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Command::NewGame => "new_game",
+            Command::Flip => "flip",
+            Command::ToggleAnalysis => "toggle_analysis",
+            Command::EngineMove => "engine_move",
+            Command::ToggleAutoPlay => "toggle_auto_play",
+            Command::SaveGame => "save_game",
+            Command::LoadGame => "load_game",
+            Command::CopyPosition => "copy_position",
+            Command::ClearAnnotations => "clear_annotations",
+            Command::OpenCommandBar => "command_bar",
+            Command::Help => "help",
+
+        };
+
+    }
+
+    pub fn description(&self) -> &'static str {
+        return match self {
+            Command::NewGame => "New Game",
+            Command::Flip => "Flip Board",
+            Command::ToggleAnalysis => "Analysis On/Off",
+            Command::EngineMove => "Engine Move",
+            Command::ToggleAutoPlay => "Auto Play On/Off",
+            Command::SaveGame => "Save Game",
+            Command::LoadGame => "Load Game",
+            Command::CopyPosition => "Copy Position",
+            Command::ClearAnnotations => "Clear Annotations",
+            Command::OpenCommandBar => "Command Bar",
+            Command::Help => "Shortcuts",
+
+        };
+
+    }
+
+    // Function keys and Ctrl+L by default so none of them types a character
+    pub fn default_shortcut(&self) -> Shortcut {
+        let key = match self {
+            Command::NewGame => KeyCode::F2,
+            Command::Flip => KeyCode::F3,
+            Command::ToggleAnalysis => KeyCode::F4,
+            Command::EngineMove => KeyCode::F5,
+            Command::ToggleAutoPlay => KeyCode::F6,
+            Command::SaveGame => KeyCode::F7,
+            Command::LoadGame => KeyCode::F8,
+            Command::CopyPosition => KeyCode::F9,
+            Command::ClearAnnotations => KeyCode::F10,
+            Command::OpenCommandBar => KeyCode::L,
+            Command::Help => KeyCode::F1,
+
+        };
+
+        return Shortcut { key, ctrl: *self == Command::OpenCommandBar };
+
+    }
+
+}
+
+// A key, optionally held together with Ctrl
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shortcut {
+    pub key: KeyCode,
+    pub ctrl: bool,
+
+}
+
+impl Shortcut {
+    pub fn pressed(&self) -> bool {
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        return is_key_pressed(self.key) && ctrl == self.ctrl;
+
+    }
+
+    pub fn shortcut_str(&self) -> String {
+        let key = KEY_NAMES.iter().find(|(_, key)| *key == self.key).map(|(name, _)| *name).unwrap_or("?");
+        if self.ctrl {
+            return format!("Ctrl+{}", key);
+
+        }
+
+        return key.to_string();
+
+    }
+
+    // Reads a shortcut like "F5" or "Ctrl+S", ignoring case
+    pub fn parse(data: &str) -> Option<Shortcut> {
+        let data = data.trim();
+        let (ctrl, key_name) = match data.split_once('+') {
+            Some((modifier, key_name)) if modifier.trim().eq_ignore_ascii_case("ctrl") => (true, key_name.trim()),
+            Some(_) => { return None },
+            None => (false, data),
+
+        };
+
+        let key = KEY_NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(key_name)).map(|(_, key)| *key)?;
+
+        return Some(Shortcut { key, ctrl });
+
+    }
+
+}
+
+// The shortcut bound to every command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcuts {
+    bindings: Vec<(Command, Shortcut)>,
+
+}
+
+impl Shortcuts {
+    pub fn new() -> Shortcuts {
+        return Shortcuts {
+            bindings: COMMANDS.iter().map(|command| (*command, command.default_shortcut())).collect(),
+
+        };
+
+    }
+
+    pub fn get(&self, command: Command) -> Shortcut {
+        return self.bindings.iter().find(|(c, _)| *c == command).map(|(_, shortcut)| *shortcut).unwrap_or(command.default_shortcut());
+
+    }
+
+    pub fn set(&mut self, command: Command, shortcut: Shortcut) {
+        for binding in self.bindings.iter_mut() {
+            if binding.0 == command {
+                binding.1 = shortcut;
+
+            }
+
+        }
+
+    }
+
+    // Sets a binding from a `name|shortcut` settings value
+    pub fn set_from_str(&mut self, data: &str) {
+        if let Some((name, shortcut)) = data.split_once('|') {
+            let command = COMMANDS.iter().find(|command| command.name() == name.trim());
+            if let (Some(command), Some(shortcut)) = (command, Shortcut::parse(shortcut)) {
+                self.set(*command, shortcut);
+
+            }
+
+        }
+
+    }
+
+    pub fn settings_strs(&self) -> Vec<String> {
+        return self.bindings.iter().map(|(command, shortcut)| format!("{}|{}", command.name(), shortcut.shortcut_str())).collect();
+
+    }
+
+    // The command whose shortcut was pressed this frame
+    pub fn pressed(&self) -> Option<Command> {
+        return self.bindings.iter().find(|(_, shortcut)| shortcut.pressed()).map(|(command, _)| *command);
+
+    }
+
+}
+
+// Tracks whether a text input has the keyboard focus, so keys typed into it aren't used as shortcuts
+// macroquad keeps the focused input private, so this follows the same rules it uses:
+// an input takes the focus when it is clicked, loses it on a click anywhere else
+// and only receives keys while its window is focused
+pub struct TextFocus {
+    typing: bool,
+    clicked: bool, // An input in the focused window was clicked this frame
+    focused_input: bool, // The focused window had an input this frame
+
+}
+
+impl TextFocus {
+    pub fn new() -> TextFocus {
+        return TextFocus {
+            typing: false,
+            clicked: false,
+            focused_input: false,
+
+        };
+
+    }
+
+    pub fn typing(&self) -> bool {
+        return self.typing;
+
+    }
+
+    // Draws a text input, used in place of `Ui::input_text`
+    pub fn input_text(&mut self, ui: &mut Ui, id: Id, label: &str, data: &mut String) {
+        ui.input_text(id, label, data);
+        if !ui.active_window_focused() {
+            return;
+
+        }
+
+        self.focused_input = true;
+
+        // The cursor is left at the top right of the input, which fills the width of the window
+        // A click on a window focuses it, so a click over the UI in the focused window is inside it
+        let end = ui.canvas().cursor();
+        let (x, y) = mouse_position();
+        let over_ui = ui.is_mouse_over(vec2(x, y));
+        if is_mouse_button_pressed(MouseButton::Left) && over_ui && x <= end.x && y >= end.y && y <= end.y + INPUT_HEIGHT {
+            self.clicked = true;
+
+        }
+
+    }
+
+    // Gives the focus to an input without a click, after `Ui::set_input_focus`
+    pub fn focus(&mut self) {
+        self.typing = true;
+
+    }
+
+    // Called once all the windows have been drawn
+    pub fn end_frame(&mut self) {
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.typing = self.clicked;
+
+        }
+
+        // Closing the window or focusing another one takes the keys away from the input
+        self.typing &= self.focused_input;
+
+        self.clicked = false;
+        self.focused_input = false;
+
+    }
+
+}
@@ -0,0 +1,305 @@
+use macroquad::prelude::*;
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::settings::UserSettings;
+
+// Constants
+pub const THEMES_FILE: &str = "themes.txt";
+pub const DEFAULT_THEME: &str = "Light";
+
+// Arrow colors from the Okabe-Ito palette, which stay distinct for every common kind of color blindness
+pub const COLORBLIND_ACCENT: Color = Color::new(0.0, 0.447, 0.698, 1.0); // Blue
+pub const COLORBLIND_HISTORY_MOVE: Color = Color::new(0.902, 0.624, 0.0, 1.0); // Orange
+pub const COLORBLIND_ANNOTATIONS: [Color; 4] = [
+    Color::new(0.0, 0.620, 0.451, 0.8), // Bluish green
+    Color::new(0.835, 0.369, 0.0, 0.8), // Vermillion
+    Color::new(0.337, 0.706, 0.914, 0.8), // Sky blue
+    Color::new(0.941, 0.894, 0.259, 0.8), // Yellow
+
+];
+
+// How the pieces show how far they move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PieceStyle {
+    Rings, // One ring per step
+    Numerals,
+    Pips, // Dots like on a die
+
+}
+
+pub const PIECE_STYLES: [PieceStyle; 3] = [PieceStyle::Rings, PieceStyle::Numerals, PieceStyle::Pips];
+
+impl PieceStyle {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            PieceStyle::Rings => "Rings",
+            PieceStyle::Numerals => "Numerals",
+            PieceStyle::Pips => "Pips",
+
+        };
+
+    }
+
+}
+
+// Where the pips of a piece go, relative to its center
+pub fn pip_offsets(piece_type: usize) -> Vec<(f32, f32)> {
+    return match piece_type {
+        1 => vec![(0.0, 0.0)],
+        2 => vec![(-9.0, -9.0), (9.0, 9.0)],
+        3 => vec![(-11.0, -11.0), (0.0, 0.0), (11.0, 11.0)],
+        _ => vec![],
+
+    };
+
+}
+
+// Every color used to draw the board, the pieces and the marks on them
+// Colors that are drawn on the board are named after what they mark,
+// `text` is used for everything drawn on the background around the board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+
+    pub background: Color,
+    pub board: Color,
+    pub gridspot: Color,
+    pub piece: Color,
+    pub piece_detail: Color, // Gaps between the rings, numerals and pips
+
+    pub text: Color,
+    pub label: Color, // Coordinates
+    pub index: Color, // Square indices
+
+    pub accent: Color, // Best move arrow, player to move and running clock
+    pub history_move: Color,
+    pub selection: Color,
+    pub drop_target: Color,
+    pub last_move: Color,
+    pub threat: Color,
+    pub annotations: [Color; 4], // Same order as `ANNOTATION_COLORS`
+
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        let board = Color::new(160.0/255.0, 149.0/255.0, 115.0/255.0 , 1.0); // Hex: #a09573
+        return Theme {
+            name: "Light".to_string(),
+
+            background: LIGHTGRAY,
+            board,
+            gridspot: Color::new(175.0/255.0, 163.0/255.0, 126.0/255.0, 1.0), // Hex: #afa37e
+            piece: BLACK,
+            piece_detail: board,
+
+            text: BLACK,
+            label: Color::new(0.0, 0.0, 0.0, 0.6),
+            index: DARKBLUE,
+
+            accent: Color::new(0.0, 1.0, 0.0, 1.0),
+            history_move: RED,
+            selection: DARKBLUE,
+            drop_target: BLACK,
+            last_move: Color::new(1.0, 0.85, 0.0, 0.45),
+            threat: Color::new(0.9, 0.0, 0.0, 0.45),
+            annotations: [
+                Color::new(0.0, 0.6, 0.0, 0.8),
+                Color::new(0.8, 0.0, 0.0, 0.8),
+                Color::new(0.0, 0.3, 0.8, 0.8),
+                Color::new(0.9, 0.7, 0.0, 0.8),
+
+            ],
+
+        };
+
+    }
+
+    pub fn dark() -> Theme {
+        let board = Color::new(0.23, 0.24, 0.27, 1.0);
+        return Theme {
+            name: "Dark".to_string(),
+
+            background: Color::new(0.11, 0.11, 0.13, 1.0),
+            board,
+            gridspot: Color::new(0.30, 0.31, 0.35, 1.0),
+            piece: Color::new(0.88, 0.88, 0.86, 1.0),
+            piece_detail: board,
+
+            text: Color::new(0.88, 0.88, 0.86, 1.0),
+            label: Color::new(1.0, 1.0, 1.0, 0.55),
+            index: Color::new(0.55, 0.75, 1.0, 1.0),
+
+            accent: Color::new(0.3, 0.9, 0.4, 1.0),
+            history_move: Color::new(1.0, 0.4, 0.4, 1.0),
+            selection: Color::new(0.45, 0.65, 1.0, 1.0),
+            drop_target: WHITE,
+            last_move: Color::new(1.0, 0.85, 0.2, 0.35),
+            threat: Color::new(1.0, 0.2, 0.2, 0.45),
+            annotations: [
+                Color::new(0.3, 0.85, 0.3, 0.8),
+                Color::new(1.0, 0.3, 0.3, 0.8),
+                Color::new(0.35, 0.6, 1.0, 0.8),
+                Color::new(1.0, 0.85, 0.2, 0.8),
+
+            ],
+
+        };
+
+    }
+
+    pub fn high_contrast() -> Theme {
+        return Theme {
+            name: "High Contrast".to_string(),
+
+            background: BLACK,
+            board: WHITE,
+            gridspot: Color::new(0.78, 0.78, 0.78, 1.0),
+            piece: BLACK,
+            piece_detail: WHITE,
+
+            text: WHITE,
+            label: BLACK,
+            index: Color::new(0.0, 0.0, 0.8, 1.0),
+
+            accent: Color::new(0.0, 0.75, 0.0, 1.0),
+            history_move: Color::new(0.9, 0.0, 0.0, 1.0),
+            selection: Color::new(0.0, 0.0, 0.9, 1.0),
+            drop_target: BLACK,
+            last_move: Color::new(1.0, 0.8, 0.0, 0.7),
+            threat: Color::new(1.0, 0.0, 0.0, 0.7),
+            annotations: [
+                Color::new(0.0, 0.6, 0.0, 1.0),
+                Color::new(0.85, 0.0, 0.0, 1.0),
+                Color::new(0.0, 0.25, 0.9, 1.0),
+                Color::new(0.85, 0.6, 0.0, 1.0),
+
+            ],
+
+        };
+
+    }
+
+    // The same theme with the arrow and annotation colors swapped for colorblind safe ones
+    pub fn with_colorblind_arrows(&self) -> Theme {
+        let mut theme = self.clone();
+        theme.accent = COLORBLIND_ACCENT;
+        theme.history_move = COLORBLIND_HISTORY_MOVE;
+        theme.annotations = COLORBLIND_ANNOTATIONS;
+
+        return theme;
+
+    }
+
+    // Sets a color from a `key=#rrggbb` line of the themes file
+    fn set_color(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let color = parse_color(value).ok_or(format!("Invalid color for {}: {}", key, value))?;
+        match key {
+            "background" => { self.background = color },
+            "board" => { self.board = color },
+            "gridspot" => { self.gridspot = color },
+            "piece" => { self.piece = color },
+            "piece_detail" => { self.piece_detail = color },
+            "text" => { self.text = color },
+            "label" => { self.label = color },
+            "index" => { self.index = color },
+            "accent" => { self.accent = color },
+            "history_move" => { self.history_move = color },
+            "selection" => { self.selection = color },
+            "drop_target" => { self.drop_target = color },
+            "last_move" => { self.last_move = color },
+            "threat" => { self.threat = color },
+            "annotation_green" => { self.annotations[0] = color },
+            "annotation_red" => { self.annotations[1] = color },
+            "annotation_blue" => { self.annotations[2] = color },
+            "annotation_yellow" => { self.annotations[3] = color },
+            _ => { return Err(format!("Unknown theme color: {}", key)) },
+
+        }
+
+        return Ok(());
+
+    }
+
+    // The themes file is kept next to the settings file
+    pub fn custom_path() -> PathBuf {
+        return UserSettings::path().with_file_name(THEMES_FILE);
+
+    }
+
+}
+
+// The built in themes followed by the custom themes, and the error if the themes file can't be read
+// A missing themes file is not an error
+pub fn load_themes() -> (Vec<Theme>, Option<String>) {
+    let mut themes = vec![Theme::light(), Theme::dark(), Theme::high_contrast()];
+
+    let path = Theme::custom_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return (themes, None);
+
+    };
+
+    return match parse_themes(&data) {
+        Ok(custom) => {
+            themes.extend(custom);
+            (themes, None)
+
+        },
+        Err(err) => (themes, Some(format!("{}: {}", path.display(), err))),
+
+    };
+
+}
+
+// Reads custom themes
+// Every theme starts with a `name=` line followed by its colors, colors that aren't given are taken from the light theme
+//
+// name=Ocean
+// background=#1b2838
+// board=#2a475e
+pub fn parse_themes(data: &str) -> Result<Vec<Theme>, String> {
+    let mut themes: Vec<Theme> = vec![];
+    for (line_idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+
+        }
+
+        let (key, value) = line.split_once('=').ok_or(format!("Line {}: expected key=value", line_idx + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        if key == "name" {
+            let mut theme = Theme::light();
+            theme.name = value.to_string();
+            themes.push(theme);
+            continue;
+
+        }
+
+        let theme = themes.last_mut().ok_or(format!("Line {}: the first line of a theme has to be its name", line_idx + 1))?;
+        theme.set_color(key, value).map_err(|err| format!("Line {}: {}", line_idx + 1, err))?;
+
+    }
+
+    return Ok(themes);
+
+}
+
+// Reads a color written as `#rrggbb` or `#rrggbbaa`
+pub fn parse_color(data: &str) -> Option<Color> {
+    let hex = data.trim().strip_prefix('#')?;
+    if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+        return None;
+
+    }
+
+    let channels: Vec<u8> = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect::<Option<Vec<u8>>>()?;
+    let alpha = channels.get(3).copied().unwrap_or(255);
+
+    return Some(Color::from_rgba(channels[0], channels[1], channels[2], alpha));
+
+}
@@ -1,18 +1,23 @@
-use core::panic;
 use std::os::windows::process::CommandExt;
-use std::process::{Command, Stdio, ChildStdout, ChildStdin};
-use std::io::{self, Read, Write};
+use std::process::{Child, Command, Stdio, ChildStdout, ChildStdin};
+use std::io::{self, BufRead, Read, Write};
 use std::thread::{self, JoinHandle};
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 use winapi::um::winbase::CREATE_NO_WINDOW;
 
+use crate::analysis_cache::AnalysisCache;
+use crate::search_log::SearchLog;
+use crate::clock::ClockTimes;
+use crate::ugi_log::{Direction, UgiLog};
 use crate::{DrawableBoard, Move};
 
 // Constants
 pub const MAX_PLY: f32 = 99.0; // moves
 pub const MAX_TIME: f32 =  3600.0; // seconds
+pub const HANDSHAKE_TIMEOUT: f32 = 3.0; // seconds
 
 // The mode of the engine
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -35,6 +40,25 @@ pub struct UgiEngine {
 
     pub settings: SearchSettings,
 
+    pub cache: AnalysisCache,
+    search_logs: HashMap<String, SearchLog>,
+    position_key: String,
+
+    process: Option<EngineProcess>,
+    handshake_start: Option<Instant>, // Set until the engine answers `ugi` with `ugiok`
+    pub name: String,
+    pub error: Option<String>, // Why the engine was disconnected, until the UI shows it
+
+    recived_queue: VecDeque<String>,
+
+    pub log: UgiLog,
+
+}
+
+// The channels and threads of a running engine process
+struct EngineProcess {
+    child: Child,
+
     input_sender: Sender<String>,
     ouput_reciver: Receiver<String>,
 
@@ -44,18 +68,15 @@ pub struct UgiEngine {
     writer_thread: Option<JoinHandle<()>>,
     writer_quit_sender: Sender<bool>,
 
-    recived_queue: VecDeque<String>,
-
 }
 
 impl UgiEngine {
-    pub fn new(engine_path: &str) -> UgiEngine {
-        let mut engine_process = Command::new(engine_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .creation_flags(CREATE_NO_WINDOW)
+    // Starts the engine described by `config` and sends the ugi handshake
+    // The engine can't search until it answers, see `update_handshake`
+    pub fn new(config: &EngineConfig) -> Result<UgiEngine, String> {
+        let mut engine_process = config.command()
             .spawn()
-            .expect("Failed to start the process");
+            .map_err(|err| format!("Failed to start {}: {}", config.path, err))?;
 
         let stdout = engine_process.stdout.take().unwrap();
         let stdin = engine_process.stdin.take().unwrap();
@@ -77,6 +98,37 @@ impl UgiEngine {
 
         });
 
+        let process = EngineProcess {
+            child: engine_process,
+
+            input_sender,
+            ouput_reciver,
+
+            reader_thread: Some(reader_thread),
+            reader_quit_sender: quit_sender_1,
+
+            writer_thread: Some(writer_thread),
+            writer_quit_sender: quit_sender_2,
+
+        };
+
+        let mut engine = UgiEngine::from_process(Some(process));
+        engine.name = config.name.clone();
+        engine.send("ugi");
+        engine.handshake_start = Some(Instant::now());
+
+        return Ok(engine);
+
+    }
+
+    // An engine without a process, used when no engine is configured
+    // Commands are ignored and searches never start
+    pub fn disconnected() -> UgiEngine {
+        return UgiEngine::from_process(None);
+
+    }
+
+    fn from_process(process: Option<EngineProcess>) -> UgiEngine {
         return UgiEngine {
             mode: Mode::Disabled,
             searching: false,
@@ -86,20 +138,25 @@ impl UgiEngine {
 
             settings: SearchSettings {
                 max_ply: MAX_PLY as f32,
-                max_time: MAX_TIME as f32,
+                max_time: (MAX_TIME, MAX_TIME),
+                max_nodes: None,
+                mate_in: None,
+                clock: None,
 
             },
 
-            input_sender,
-            ouput_reciver,
+            cache: AnalysisCache::new(),
+            search_logs: HashMap::new(),
+            position_key: String::new(),
 
-            reader_thread: Some(reader_thread),
-            reader_quit_sender: quit_sender_1,
-
-            writer_thread: Some(writer_thread),
-            writer_quit_sender: quit_sender_2,
+            process,
+            handshake_start: None,
+            name: String::new(),
+            error: None,
 
             recived_queue: VecDeque::new(),
+
+            log: UgiLog::new(),
   
         };
 
@@ -107,22 +164,41 @@ impl UgiEngine {
 
     // ===== Communication functions =====
 
+    // Whether the engine is running and has answered the handshake
+    pub fn connected(&self) -> bool {
+        return self.process.is_some() && self.handshake_start.is_none();
+
+    }
+
+    // Commands to an engine that stopped reading them disconnect it
     pub fn send(&mut self, cmd: &str) {
-        let x = self.input_sender.send(cmd.to_string());
-        if x.is_err() {
-            panic!("Failed to send command: {}", cmd);
+        if let Some(process) = &self.process {
+            self.log.push(Direction::ToEngine, cmd);
+
+            if process.input_sender.send(cmd.to_string()).is_err() {
+                self.disconnect(&format!("{} stopped responding", self.name));
+
+            }
 
         }
 
     }
 
     fn try_recive(&mut self) {
-        match self.ouput_reciver.try_recv() {
-            Ok(s) => {
-                self.recived_queue.push_front(s.clone());
+        if let Some(process) = &self.process {
+            match process.ouput_reciver.try_recv() {
+                Ok(s) => {
+                    self.log.push(Direction::FromEngine, &s);
+                    self.recived_queue.push_front(s.clone());
+
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnect(&format!("{} exited", self.name));
+
+                }
+                Err(TryRecvError::Empty) => {}
 
             }
-            Err(_) => {}
 
         }
 
@@ -160,16 +236,146 @@ impl UgiEngine {
 
     pub fn quit(&mut self) {
         self.send("quit");
-        
-        self.reader_quit_sender.send(true).unwrap();
-        self.writer_quit_sender.send(true).unwrap();
+        self.close();
+
+    }
 
-        self.reader_thread.take().unwrap().join().unwrap();
-        self.writer_thread.take().unwrap().join().unwrap();
+    // Stops the engine without asking it to quit and keeps the reason to show it
+    fn disconnect(&mut self, reason: &str) {
+        self.close();
+        self.mode = Mode::Disabled;
+        self.searching = false;
+        self.error = Some(reason.to_string());
+
+    }
+
+    // Ends the process and its threads, the threads may already have stopped if the engine exited
+    fn close(&mut self) {
+        self.handshake_start = None;
+
+        if let Some(mut process) = self.process.take() {
+            let _ = process.writer_quit_sender.send(true);
+            if let Some(writer_thread) = process.writer_thread.take() {
+                let _ = writer_thread.join();
+
+            }
+
+            // The reader is blocked until the engine closes its output
+            let _ = process.reader_quit_sender.send(true);
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+            if let Some(reader_thread) = process.reader_thread.take() {
+                let _ = reader_thread.join();
+
+            }
+
+        }
+
+    }
+
+    // Waits for `ugiok` without blocking the UI, disconnecting an engine that doesn't answer in time
+    fn update_handshake(&mut self) {
+        let Some(start) = self.handshake_start else {
+            return;
+
+        };
+
+        while let Some(line) = self.recive() {
+            if let Some(id_name) = line.strip_prefix("id name ") {
+                self.name = id_name.trim().to_string();
+
+            } else if line.trim() == "ugiok" {
+                self.handshake_start = None;
+                return;
+
+            }
+
+        }
+
+        if self.process.is_some() && start.elapsed().as_secs_f32() > HANDSHAKE_TIMEOUT {
+            self.disconnect(&format!("{} is not a UGI engine, it didn't answer ugi", self.name));
+
+        }
+
+    }
+
+    // Launches an engine, checks that it answers the ugi handshake and closes it again
+    // Returns the engine's name if it sent one
+    pub fn test(config: &EngineConfig) -> Result<String, String> {
+        let mut engine_process = config.command()
+            .spawn()
+            .map_err(|err| format!("Failed to start {}: {}", config.path, err))?;
+
+        let stdout = engine_process.stdout.take().unwrap();
+        let mut stdin = engine_process.stdin.take().unwrap();
+
+        let (line_sender, line_reciver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => { if line_sender.send(line).is_err() { break } },
+                    Err(_) => break,
+
+                }
+
+            }
+
+        });
+
+        let mut result = Err("No response to ugi".to_string());
+        if stdin.write_all(b"ugi\n").is_ok() {
+            let mut name = config.name.clone();
+            let start = Instant::now();
+            let timeout = Duration::from_secs_f32(HANDSHAKE_TIMEOUT);
+
+            while start.elapsed() < timeout {
+                match line_reciver.recv_timeout(timeout.saturating_sub(start.elapsed())) {
+                    Ok(line) => {
+                        if let Some(id_name) = line.strip_prefix("id name ") {
+                            name = id_name.trim().to_string();
+
+                        } else if line.trim() == "ugiok" {
+                            result = Ok(name);
+                            break;
+
+                        }
+
+                    },
+                    Err(_) => break,
+
+                }
+
+            }
+
+        }
+
+        let _ = stdin.write_all(b"quit\n");
+        let _ = engine_process.kill();
+        let _ = engine_process.wait();
+
+        return result;
+
+    }
+
+    // Runs `test` on its own thread so the UI keeps running while the engine starts
+    pub fn test_in_background(config: &EngineConfig) -> Receiver<Result<String, String>> {
+        let (result_sender, result_reciver) = mpsc::channel();
+        let config = config.clone();
+        thread::spawn(move || {
+            let _ = result_sender.send(UgiEngine::test(&config));
+
+        });
+
+        return result_reciver;
 
     }
 
     pub fn new_search(&mut self, search_purpose: Mode, drawable_board: &DrawableBoard) {
+        if !self.connected() {
+            return;
+
+        }
+
         if self.searching {
             self.send("stop");
             self.wait_for_search();
@@ -182,19 +388,24 @@ impl UgiEngine {
         };
         self.send(setcmd.as_str());
 
-        if search_purpose == Mode::Analysis {
-            self.settings.max_ply = MAX_PLY;
-            self.settings.max_time = MAX_TIME;
+        // Show any cached result for this position right away
+        self.position_key = AnalysisCache::key(&drawable_board.boardstate_str(), self.side);
+        self.best_search = self.cache.get(&self.position_key).cloned().unwrap_or(SearchInfo::new());
 
-        }
-        
-        let maxtime_cmd = format!("setoption maxTime {}", self.settings.max_time);
+        // Options stay set between searches, so analysis resets the limits of the last move to the maximum
+        let (max_time, max_ply) = match (search_purpose, self.side) {
+            (Mode::Analysis, _) => (MAX_TIME, MAX_PLY),
+            (_, 1.0) => (self.settings.max_time.0, self.settings.max_ply),
+            _ => (self.settings.max_time.1, self.settings.max_ply),
+
+        };
+        let maxtime_cmd = format!("setoption maxTime {}", max_time);
         self.send(maxtime_cmd.as_str());
 
-        let maxply_cmd = format!("setoption maxPly {}", self.settings.max_ply);
+        let maxply_cmd = format!("setoption maxPly {}", max_ply);
         self.send(maxply_cmd.as_str());
 
-        self.send("go");
+        self.send(self.go_cmd(search_purpose).as_str());
     
         self.mode = search_purpose;
         self.searching = true;
@@ -204,9 +415,51 @@ impl UgiEngine {
     
     }
 
+    // Builds the go command with the node, mate and clock limits
+    // The engine always sees itself as P1, so the clock times are swapped when searching for P2
+    fn go_cmd(&self, search_purpose: Mode) -> String {
+        if search_purpose == Mode::Analysis {
+            return "go infinite".to_string();
+
+        }
+
+        let mut go_cmd = "go".to_string();
+
+        if let Some(max_nodes) = self.settings.max_nodes {
+            go_cmd.push_str(&format!(" nodes {}", max_nodes));
+
+        }
+        if let Some(mate_in) = self.settings.mate_in {
+            go_cmd.push_str(&format!(" mate {}", mate_in));
+
+        }
+
+        if let Some(times) = self.settings.clock {
+            let (own_time, opp_time) = match self.side {
+                1.0 => times.remaining,
+                _ => (times.remaining.1, times.remaining.0),
+
+            };
+
+            go_cmd.push_str(&format!(" p1time {} p2time {} inc {}", (own_time * 1000.0) as u64, (opp_time * 1000.0) as u64, (times.increment * 1000.0) as u64));
+            if let Some(moves_to_go) = times.moves_to_go {
+                go_cmd.push_str(&format!(" movestogo {}", moves_to_go));
+
+            }
+
+        }
+
+        return go_cmd;
+
+    }
+
     pub fn wait_for_search(&mut self) {
         loop {
             self.try_recive();
+            if self.process.is_none() {
+                break;
+
+            }
             if self.recived_queue.len() == 0 {
                 continue;
 
@@ -224,6 +477,19 @@ impl UgiEngine {
     
     // Main update function
     pub fn update(&mut self, drawable_board: &mut DrawableBoard) {
+        if self.handshake_start.is_some() {
+            self.update_handshake();
+
+            // Analysis starts as soon as the engine is ready
+            if self.connected() && !drawable_board.game_over() {
+                self.new_search(Mode::Analysis, drawable_board);
+
+            }
+
+            return;
+
+        }
+
         if self.mode == Mode::Disabled {
             self.best_search = SearchInfo::new();
 
@@ -241,37 +507,48 @@ impl UgiEngine {
                 Some(&"bestmove") => {
                     self.searching = false;
 
-                    match self.mode {
-                        Mode::Single => {
-                            let best_move = self.parse_bestmove_str(cmds.get(1).unwrap());
+                    if self.mode != Mode::Single && self.mode != Mode::Auto {
+                        return;
 
-                            drawable_board.make_move(best_move);
-                            self.stop();
-   
-                        },
-                        Mode::Auto => {
-                            let best_move = self.parse_bestmove_str(cmds.get(1).unwrap());
+                    }
 
-                            drawable_board.make_move(best_move);
-    
-                            if drawable_board.game_over() {
-                                self.stop();
-    
-                            } else {
-                                self.flip_side();
-                                self.new_search(Mode::Auto, drawable_board);
-                                std::thread::sleep(std::time::Duration::from_millis(100)); // Min delay between moves 
+                    // A move that can't be read would leave the game waiting forever
+                    let best_move = match self.parse_bestmove_str(cmds.get(1).unwrap_or(&"")) {
+                        Ok(best_move) => best_move,
+                        Err(err) => {
+                            self.disconnect(&format!("{} sent an invalid bestmove: {}", self.name, err));
+                            return;
 
-                            }
-                                
                         },
-                        _ => {}
+
+                    };
+
+                    drawable_board.make_move(best_move);
+
+                    // The next auto play search is started by `continue_auto` once the move has pressed the clock
+                    if self.mode == Mode::Single || drawable_board.game_over() {
+                        self.stop();
+
+                    } else {
+                        self.flip_side();
+                        std::thread::sleep(std::time::Duration::from_millis(100)); // Min delay between moves 
 
                     }
 
                 },
                 Some(&"info") => {
-                    self.best_search = self.parse_info_str(data.as_str());
+                    // Only replace the shown search if it is deeper than the cached one
+                    // Lines that can't be read are skipped, the next one usually replaces them
+                    let Ok(search_info) = self.parse_info_str(data.as_str()) else {
+                        return;
+
+                    };
+                    self.search_logs.entry(self.position_key.clone()).or_insert(SearchLog::new()).record(&search_info);
+
+                    if self.cache.insert(&self.position_key, &search_info) || self.cache.get(&self.position_key).is_none() {
+                        self.best_search = search_info;
+
+                    }
 
                 },
                 _ => {}
@@ -282,86 +559,83 @@ impl UgiEngine {
 
     }
 
+    // Starts the next search of a running auto game after the engine moved
+    // Called after the move reached the history and the clock, so `go` is sent with the clock times after the move
+    pub fn continue_auto(&mut self, drawable_board: &mut DrawableBoard) {
+        if self.mode == Mode::Auto && !self.searching && !drawable_board.game_over() {
+            self.new_search(Mode::Auto, drawable_board);
+
+        }
+
+    }
+
     // ===== Helper functions =====
-    
-    pub fn parse_bestmove_str(&self, raw_move: &str) -> Move {
-        let raw_mv_data: Vec<&str> = raw_move.split("|").collect();
 
+    // The per-depth log of the position that was searched last
+    pub fn search_log(&self) -> Option<&SearchLog> {
+        return self.search_logs.get(&self.position_key);
+
+    }
+    
+    // Reads a move sent as `start|end` or `start|end|drop`
+    pub fn parse_bestmove_str(&self, raw_move: &str) -> Result<Move, String> {
         let mut mv = vec![];
-        for i in 0..raw_mv_data.len() {
-            mv.push(raw_mv_data[i].parse::<usize>().unwrap());
+        for square in raw_move.split('|') {
+            match square.parse::<usize>() {
+                Ok(square) if square <= 37 => mv.push(square),
+                _ => { return Err(format!("Invalid move: {}", raw_move)) },
+
+            }
+
+        }
+
+        if mv.len() != 2 && mv.len() != 3 {
+            return Err(format!("Invalid move: {}", raw_move));
 
         }
 
         if self.side == -1.0 {
-            return flip_move(mv);
+            return Ok(flip_move(mv));
 
         }
-        return mv;
+        return Ok(mv);
 
     }
 
-    pub fn parse_info_str(&self, info_str: &str) -> SearchInfo {
+    // Reads an `info` line made of `name value` pairs, unknown names are ignored
+    pub fn parse_info_str(&self, info_str: &str) -> Result<SearchInfo, String> {
         let mut search_info = SearchInfo::new();
 
         let mut raw_cmds: Vec<&str> = info_str.split_whitespace().collect();
-        if raw_cmds.get(0) == Some(&"info") {
-            raw_cmds.remove(0);
-
-            let cmd_groups = raw_cmds.chunks(2).map(|x| [x[0], x[1]]).collect::<Vec<[&str; 2]>>();
-            for group in cmd_groups {
-                match group[0] {
-                    "ply" => {
-                        let ply = group[1].parse::<f64>().unwrap();
-                        search_info.ply = Some(ply);
-
-                    },
-                    "bestmove" => {
-                        let best_move = self.parse_bestmove_str(group[1]);
-                        search_info.best_move = Some(best_move);
-
-                    },
-                    "score" => {
-                        let score = group[1].parse::<f64>().unwrap();
-                        search_info.score = Some(score);
+        if raw_cmds.first() != Some(&"info") {
+            return Err(format!("Not an info line: {}", info_str));
 
-                    },
-                    "nodes" => {
-                        let nodes = group[1].parse::<f64>().unwrap();
-                        search_info.nodes = Some(nodes);
-
-                    },
-                    "nps" => {
-                        let nps = group[1].parse::<f64>().unwrap();
-                        search_info.nps = Some(nps);
-
-                    },
-                    "abf" => {
-                        let abf = group[1].parse::<f64>().unwrap();
-                        search_info.abf = Some(abf);
+        }
+        raw_cmds.remove(0);
 
-                    },
-                    "beta_cuts" => {
-                        let beta_cuts = group[1].parse::<f64>().unwrap();
-                        search_info.beta_cuts = Some(beta_cuts);
+        if !raw_cmds.len().is_multiple_of(2) {
+            return Err(format!("Missing value in info line: {}", info_str));
 
-                    },
-                    "time" => {
-                        let time = group[1].parse::<f64>().unwrap();
-                        search_info.time = Some(time);
+        }
 
-                    },
-                    _ => {}
-                
-                }
+        let number = |value: &str| value.parse::<f64>().map_err(|_| format!("Invalid number in info line: {}", value));
+        for group in raw_cmds.chunks(2) {
+            match group[0] {
+                "ply" => { search_info.ply = Some(number(group[1])?) },
+                "bestmove" => { search_info.best_move = Some(self.parse_bestmove_str(group[1])?) },
+                "score" => { search_info.score = Some(number(group[1])?) },
+                "nodes" => { search_info.nodes = Some(number(group[1])?) },
+                "nps" => { search_info.nps = Some(number(group[1])?) },
+                "abf" => { search_info.abf = Some(number(group[1])?) },
+                "beta_cuts" => { search_info.beta_cuts = Some(number(group[1])?) },
+                "time" => { search_info.time = Some(number(group[1])?) },
+                _ => {}
 
             }
 
-            return search_info;
-
         }
 
-        return SearchInfo::new();
+        return Ok(search_info);
 
     }
 
@@ -414,10 +688,18 @@ impl UgiReader {
 
         loop {
             match stdout_reader.read(&mut stdout_buffer) {
+                // The engine exited
+                Ok(0) => {
+                    break;
+
+                }
                 Ok(n) => {
                     let output = String::from_utf8_lossy(&stdout_buffer[..n]).to_string();
                     for l in output.lines() {
-                        self.data_out.send(l.to_string()).unwrap();
+                        if self.data_out.send(l.to_string()).is_err() {
+                            return;
+
+                        }
 
                     }
 
@@ -464,8 +746,12 @@ impl UgiWriter {
     pub fn start(&mut self, mut stdin: ChildStdin) {
         loop {
             match self.data_in.try_recv() {
+                // Stops when the engine closed its input, `UgiEngine::send` notices the closed channel
                 Ok(s) => {
-                    stdin.write_all(format!("{}\n", s).as_bytes()).unwrap();
+                    if stdin.write_all(format!("{}\n", s).as_bytes()).is_err() {
+                        return;
+
+                    }
 
                 }
                 Err(_) => {}
@@ -488,11 +774,90 @@ impl UgiWriter {
 }
 
 
+// A configured engine binary and how to launch it
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineConfig {
+    pub name: String,
+    pub path: String,
+    pub args: Vec<String>,
+    pub working_dir: String,
+
+}
+
+impl EngineConfig {
+    pub fn new(name: &str, path: &str) -> EngineConfig {
+        return EngineConfig {
+            name: name.to_string(),
+            path: path.to_string(),
+            args: vec![],
+            working_dir: String::new(),
+
+        };
+
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.path);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .creation_flags(CREATE_NO_WINDOW);
+
+        if !self.working_dir.is_empty() {
+            command.current_dir(&self.working_dir);
+
+        }
+
+        return command;
+
+    }
+
+}
+
+// Splits engine arguments on spaces, keeping text in double quotes together: `--book "my book.txt"`
+pub fn split_args(data: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    let mut quoted = false;
+    let mut has_arg = false; // `""` is an empty argument
+
+    for c in data.chars() {
+        if c == '"' {
+            quoted = !quoted;
+            has_arg = true;
+
+        } else if c.is_whitespace() && !quoted {
+            if has_arg {
+                args.push(std::mem::take(&mut arg));
+                has_arg = false;
+
+            }
+
+        } else {
+            arg.push(c);
+            has_arg = true;
+
+        }
+
+    }
+    if has_arg {
+        args.push(arg);
+
+    }
+
+    return args;
+
+}
+
 // Stores search settings
 #[derive(Debug, Clone)]
 pub struct SearchSettings {
     pub max_ply: f32,
-    pub max_time: f32,
+    pub max_time: (f32, f32), // (P1, P2)
+    pub max_nodes: Option<u64>,
+    pub mate_in: Option<usize>,
+    pub clock: Option<ClockTimes>,
 
 }
 
@@ -526,4 +891,47 @@ impl SearchInfo {
 
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_keeps_quoted_text_together() {
+        assert_eq!(split_args("--book \"my book.txt\"  --threads=4"), vec!["--book", "my book.txt", "--threads=4"]);
+        assert_eq!(split_args("a \"\" b"), vec!["a", "", "b"]);
+        assert!(split_args("   ").is_empty());
+
+    }
+
+    #[test]
+    fn engine_replies_are_parsed() {
+        let mut engine = UgiEngine::disconnected();
+        assert_eq!(engine.parse_bestmove_str("4|16|22"), Ok(vec![4, 16, 22]));
+
+        let search_info = engine.parse_info_str("info ply 5 score -1.5 bestmove 1|37").unwrap();
+        assert_eq!((search_info.ply, search_info.score, search_info.best_move), (Some(5.0), Some(-1.5), Some(vec![1, 37])));
+
+        // P2's moves are sent from their side of the board
+        engine.side = -1.0;
+        assert_eq!(engine.parse_bestmove_str("0|37"), Ok(vec![35, 36]));
+
+    }
+
+    #[test]
+    fn malformed_replies_are_errors() {
+        let engine = UgiEngine::disconnected();
+        for raw_move in ["", "4", "4|x", "4|38", "1|2|3|4"] {
+            assert!(engine.parse_bestmove_str(raw_move).is_err(), "{}", raw_move);
+
+        }
+
+        for info in ["info ply", "info ply five", "info bestmove 4", "bestmove 4|16"] {
+            assert!(engine.parse_info_str(info).is_err(), "{}", info);
+
+        }
+        assert!(engine.parse_info_str("info unknown 1").is_ok());
+
+    }
+
+}
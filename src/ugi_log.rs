@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+// Constants
+pub const UGI_LOG_PATH: &str = "./ugi_log.txt";
+pub const MAX_LOG_ENTRIES: usize = 10000;
+
+// Which way a line travelled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    ToEngine,
+    FromEngine,
+
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: f64, // Seconds since the log started
+    pub direction: Direction,
+    pub line: String,
+
+}
+
+impl LogEntry {
+    // The first word of the line, like `info` or `bestmove`
+    pub fn message_type(&self) -> &str {
+        return self.line.split_whitespace().next().unwrap_or("");
+
+    }
+
+    pub fn log_str(&self) -> String {
+        let arrow = match self.direction {
+            Direction::ToEngine => ">",
+            Direction::FromEngine => "<",
+
+        };
+
+        return format!("[{:>9.3}] {} {}", self.time, arrow, self.line);
+
+    }
+
+}
+
+// Timestamped record of all traffic between the UI and the engine
+// Only the most recent `MAX_LOG_ENTRIES` lines are kept
+pub struct UgiLog {
+    pub entries: VecDeque<LogEntry>,
+    start: Instant,
+
+}
+
+impl UgiLog {
+    pub fn new() -> UgiLog {
+        return UgiLog {
+            entries: VecDeque::new(),
+            start: Instant::now(),
+
+        };
+
+    }
+
+    pub fn push(&mut self, direction: Direction, line: &str) {
+        if self.entries.len() >= MAX_LOG_ENTRIES {
+            self.entries.pop_front();
+
+        }
+
+        self.entries.push_back(LogEntry {
+            time: self.start.elapsed().as_secs_f64(),
+            direction,
+            line: line.to_string(),
+
+        });
+
+    }
+
+    // Entries matching the direction filters and, if not empty, the message type
+    pub fn filtered(&self, show_sent: bool, show_recived: bool, message_type: &str) -> Vec<&LogEntry> {
+        let message_type = message_type.trim();
+
+        return self.entries.iter().filter(|entry| {
+            let direction_ok = match entry.direction {
+                Direction::ToEngine => show_sent,
+                Direction::FromEngine => show_recived,
+
+            };
+
+            direction_ok && (message_type.is_empty() || entry.message_type() == message_type)
+
+        }).collect();
+
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let transcript: Vec<String> = self.entries.iter().map(|entry| entry.log_str()).collect();
+
+        return fs::write(path, transcript.join("\n") + "\n");
+
+    }
+
+}